@@ -0,0 +1,212 @@
+//! Canaux de discussion
+//!
+//! Chaque canal est un topic Gossipsub `<topic principal>/<nom>` qui ne
+//! transporte que les posts du canal ; réactions, modifications, profils et
+//! abonnements restent sur le topic principal. Un relais est abonné aux
+//! canaux de sa configuration, à ceux que l'administrateur a rejoints par
+//! l'API jusqu'à ce qu'il les quitte, et à ceux que ses clients WebSocket
+//! authentifiés ont rejoints tant qu'au moins l'un d'eux reste connecté :
+//! chaque `join` d'une session est compensé par un `leave`, au plus tard à
+//! la déconnexion.
+use crate::NetworkMessage;
+use libp2p::gossipsub::IdentTopic;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+pub const MAX_CHANNEL_CHARS: usize = 32;
+/// Canaux rejoints à la demande (administrateur et clients), en plus de ceux configurés
+pub const MAX_JOINED_CHANNELS: usize = 64;
+/// Canaux rejoints par une même session WebSocket
+pub const MAX_SESSION_CHANNELS: usize = 8;
+
+/// Abonnements à appliquer par la boucle libp2p
+#[derive(Debug)]
+pub enum Command {
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+#[derive(Debug)]
+pub enum ChannelError {
+    InvalidName,
+    TooMany,
+    /// Plafond de la session WebSocket atteint
+    TooManyForSession,
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelError::InvalidName => write!(
+                f,
+                "nom de canal invalide (1 à {} caractères parmi a-z, 0-9, - et _)",
+                MAX_CHANNEL_CHARS
+            ),
+            ChannelError::TooMany => write!(f, "trop de canaux rejoints (max {})", MAX_JOINED_CHANNELS),
+            ChannelError::TooManyForSession => {
+                write!(f, "trop de canaux rejoints par cette session (max {})", MAX_SESSION_CHANNELS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChannelError {}
+
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=MAX_CHANNEL_CHARS).contains(&name.len())
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Topic Gossipsub d'un canal ; `None` désigne le topic principal
+pub fn topic(main_topic: &str, channel: Option<&str>) -> IdentTopic {
+    match channel {
+        Some(channel) => IdentTopic::new(format!("{}/{}", main_topic, channel)),
+        None => IdentTopic::new(main_topic),
+    }
+}
+
+/// Topic sur lequel publier un message : celui de son canal pour un post, le
+/// topic principal pour tout le reste
+pub fn message_topic(main_topic: &str, msg: &NetworkMessage) -> IdentTopic {
+    match msg {
+        NetworkMessage::Post(post) => topic(main_topic, post.channel.as_deref()),
+        _ => topic(main_topic, None),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelInfo {
+    pub name: String,
+    /// Rejoint en permanence par la configuration du relais
+    pub configured: bool,
+    /// Rejoint par l'administrateur via l'API
+    pub pinned: bool,
+    /// Sessions WebSocket qui ont rejoint le canal
+    pub members: usize,
+}
+
+/// Canaux rejoints à la demande
+#[derive(Default)]
+struct Joined {
+    /// Sessions WebSocket membres, par canal
+    members: BTreeMap<String, usize>,
+    /// Canaux rejoints par l'administrateur, jusqu'à leur `leave`
+    pinned: BTreeSet<String>,
+}
+
+impl Joined {
+    fn contains(&self, name: &str) -> bool {
+        self.members.contains_key(name) || self.pinned.contains(name)
+    }
+}
+
+pub struct Channels {
+    configured: BTreeSet<String>,
+    joined: Mutex<Joined>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Channels {
+    pub fn new(configured: impl IntoIterator<Item = String>, commands: mpsc::UnboundedSender<Command>) -> Self {
+        Self {
+            configured: configured.into_iter().collect(),
+            joined: Mutex::new(Joined::default()),
+            commands,
+        }
+    }
+
+    /// Le relais reçoit-il les posts de ce canal ?
+    pub fn is_joined(&self, name: &str) -> bool {
+        self.configured.contains(name) || self.joined.lock().unwrap().contains(name)
+    }
+
+    /// Ajoute une session membre ; le relais s'abonne au topic si le canal
+    /// n'était pas encore suivi
+    pub fn join(&self, name: &str) -> Result<(), ChannelError> {
+        let mut joined = self.joined.lock().unwrap();
+        self.subscribe(&joined, name)?;
+        *joined.members.entry(name.to_string()).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Retire une session membre ; le relais quitte le topic si plus rien ne le retient
+    pub fn leave(&self, name: &str) {
+        let mut joined = self.joined.lock().unwrap();
+        let Some(count) = joined.members.get_mut(name) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            joined.members.remove(name);
+            self.unsubscribe(&joined, name);
+        }
+    }
+
+    /// Rejoint un canal pour le compte de l'administrateur, indépendamment des sessions
+    pub fn pin(&self, name: &str) -> Result<(), ChannelError> {
+        let mut joined = self.joined.lock().unwrap();
+        if joined.pinned.contains(name) {
+            return Ok(());
+        }
+        self.subscribe(&joined, name)?;
+        joined.pinned.insert(name.to_string());
+        Ok(())
+    }
+
+    pub fn unpin(&self, name: &str) {
+        let mut joined = self.joined.lock().unwrap();
+        if joined.pinned.remove(name) {
+            self.unsubscribe(&joined, name);
+        }
+    }
+
+    /// Vérifie le nom et le plafond, et s'abonne si le canal n'est pas encore suivi
+    fn subscribe(&self, joined: &Joined, name: &str) -> Result<(), ChannelError> {
+        if !is_valid_name(name) {
+            return Err(ChannelError::InvalidName);
+        }
+        if self.configured.contains(name) || joined.contains(name) {
+            return Ok(());
+        }
+        let on_demand = joined
+            .members
+            .keys()
+            .chain(joined.pinned.iter())
+            .filter(|n| !self.configured.contains(*n))
+            .collect::<BTreeSet<_>>()
+            .len();
+        if on_demand >= MAX_JOINED_CHANNELS {
+            return Err(ChannelError::TooMany);
+        }
+        let _ = self.commands.send(Command::Subscribe(name.to_string()));
+        Ok(())
+    }
+
+    fn unsubscribe(&self, joined: &Joined, name: &str) {
+        if !self.configured.contains(name) && !joined.contains(name) {
+            let _ = self.commands.send(Command::Unsubscribe(name.to_string()));
+        }
+    }
+
+    pub fn list(&self) -> Vec<ChannelInfo> {
+        let joined = self.joined.lock().unwrap();
+        let names: BTreeSet<&String> = self
+            .configured
+            .iter()
+            .chain(joined.members.keys())
+            .chain(joined.pinned.iter())
+            .collect();
+        names
+            .into_iter()
+            .map(|name| ChannelInfo {
+                name: name.clone(),
+                configured: self.configured.contains(name),
+                pinned: joined.pinned.contains(name),
+                members: joined.members.get(name).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,145 @@
+//! Codecs request-response génériques : messages JSON ou octets bruts,
+//! préfixés par leur longueur
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed};
+use libp2p::request_response::{Codec, ProtocolName};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+use std::marker::PhantomData;
+
+/// Nom de protocole libp2p connu à la compilation (ex. `/zeta/sync/1.0.0`)
+#[derive(Debug, Clone)]
+pub struct Protocol(pub &'static str);
+
+impl ProtocolName for Protocol {
+    fn protocol_name(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+/// Codec JSON pour un couple requête/réponse, avec une taille maximale par message
+pub struct JsonCodec<Req, Resp> {
+    max_size: usize,
+    _marker: PhantomData<fn() -> (Req, Resp)>,
+}
+
+impl<Req, Resp> JsonCodec<Req, Resp> {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Req, Resp> Clone for JsonCodec<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self::new(self.max_size)
+    }
+}
+
+async fn read_json<T, R>(io: &mut T, max_size: usize) -> io::Result<R>
+where
+    T: AsyncRead + Unpin + Send,
+    R: DeserializeOwned,
+{
+    let bytes = read_length_prefixed(io, max_size).await?;
+    serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+async fn write_frame<T>(io: &mut T, bytes: Vec<u8>) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+{
+    write_length_prefixed(io, bytes).await?;
+    io.close().await
+}
+
+#[async_trait]
+impl<Req, Resp> Codec for JsonCodec<Req, Resp>
+where
+    Req: Serialize + DeserializeOwned + Send,
+    Resp: Serialize + DeserializeOwned + Send,
+{
+    type Protocol = Protocol;
+    type Request = Req;
+    type Response = Resp;
+
+    async fn read_request<T>(&mut self, _: &Protocol, io: &mut T) -> io::Result<Req>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_json(io, self.max_size).await
+    }
+
+    async fn read_response<T>(&mut self, _: &Protocol, io: &mut T) -> io::Result<Resp>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_json(io, self.max_size).await
+    }
+
+    async fn write_request<T>(&mut self, _: &Protocol, io: &mut T, req: Req) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_frame(io, serde_json::to_vec(&req)?).await
+    }
+
+    async fn write_response<T>(&mut self, _: &Protocol, io: &mut T, res: Resp) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_frame(io, serde_json::to_vec(&res)?).await
+    }
+}
+
+/// Codec d'octets bruts, pour les échanges où un encodage JSON doublerait la
+/// taille ; requêtes et réponses ont chacune leur taille maximale
+#[derive(Clone)]
+pub struct BytesCodec {
+    max_request: usize,
+    max_response: usize,
+}
+
+impl BytesCodec {
+    pub fn new(max_request: usize, max_response: usize) -> Self {
+        Self { max_request, max_response }
+    }
+}
+
+#[async_trait]
+impl Codec for BytesCodec {
+    type Protocol = Protocol;
+    type Request = Vec<u8>;
+    type Response = Vec<u8>;
+
+    async fn read_request<T>(&mut self, _: &Protocol, io: &mut T) -> io::Result<Vec<u8>>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_length_prefixed(io, self.max_request).await
+    }
+
+    async fn read_response<T>(&mut self, _: &Protocol, io: &mut T) -> io::Result<Vec<u8>>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_length_prefixed(io, self.max_response).await
+    }
+
+    async fn write_request<T>(&mut self, _: &Protocol, io: &mut T, req: Vec<u8>) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_frame(io, req).await
+    }
+
+    async fn write_response<T>(&mut self, _: &Protocol, io: &mut T, res: Vec<u8>) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_frame(io, res).await
+    }
+}
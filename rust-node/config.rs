@@ -0,0 +1,308 @@
+//! Configuration du nœud
+//!
+//! Ordre de priorité : options CLI > variables d'environnement `ZETA_*` >
+//! fichier TOML (`--config`, `zeta.toml` par défaut) > valeurs par défaut.
+use crate::channels;
+use crate::rate_limit::RateLimitSettings;
+use clap::Parser;
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_CONFIG_FILE: &str = "zeta.toml";
+const DEFAULT_TOPIC: &str = "zeta2-social";
+/// TCP pour les relais, WebSocket pour les navigateurs js-libp2p
+const DEFAULT_RELAY_LISTEN: &[&str] = &["/ip4/0.0.0.0/tcp/4001", "/ip4/0.0.0.0/tcp/4002/ws"];
+const DEFAULT_CLIENT_LISTEN: &[&str] = &["/ip4/0.0.0.0/tcp/0"];
+const DEFAULT_WEB_BIND: &str = "0.0.0.0:3030";
+const DEFAULT_MAX_POSTS: usize = 100_000;
+
+/// Nœud du réseau social P2P Zeta
+#[derive(Debug, Parser)]
+#[command(name = "zeta-relay", version, about)]
+struct Cli {
+    /// Fichier de configuration TOML
+    #[arg(long, env = "ZETA_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Mode relais : écoute sur un port fixe et annonce son adresse
+    #[arg(long, visible_alias = "server", env = "ZETA_RELAY")]
+    relay: bool,
+
+    /// Nom affiché du nœud
+    #[arg(long, env = "ZETA_NAME")]
+    name: Option<String>,
+
+    /// Adresse libp2p d'écoute (répétable)
+    #[arg(long = "listen", env = "ZETA_LISTEN", value_delimiter = ',')]
+    listen: Vec<Multiaddr>,
+
+    /// Adresse d'écoute de l'interface web
+    #[arg(long, env = "ZETA_WEB_BIND")]
+    web_bind: Option<SocketAddr>,
+
+    /// Port de l'interface web (remplace le port de --web-bind)
+    #[arg(long, env = "ZETA_WEB_PORT")]
+    web_port: Option<u16>,
+
+    /// Répertoire contenant identity.key et les données
+    #[arg(long, env = "ZETA_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Adresse de bootstrap supplémentaire (répétable)
+    #[arg(long = "bootstrap", env = "ZETA_BOOTSTRAP", value_delimiter = ',')]
+    bootstrap: Vec<Multiaddr>,
+
+    /// Fichier listant les adresses de bootstrap
+    #[arg(long, env = "ZETA_BOOTSTRAP_FILE")]
+    bootstrap_file: Option<PathBuf>,
+
+    /// Topic Gossipsub
+    #[arg(long, env = "ZETA_TOPIC")]
+    topic: Option<String>,
+
+    /// Canal auquel le relais reste toujours abonné (répétable)
+    #[arg(long = "channel", env = "ZETA_CHANNELS", value_delimiter = ',')]
+    channels: Vec<String>,
+
+    /// Jeton requis (en-tête `Authorization: Bearer`) par l'API de modération et la signature par le relais
+    #[arg(long, env = "ZETA_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+
+    /// Nombre de posts conservés ; au-delà, les plus anciens sont supprimés
+    #[arg(long, env = "ZETA_MAX_POSTS")]
+    max_posts: Option<usize>,
+}
+
+/// Contenu du fichier TOML ; tous les champs sont optionnels
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    relay: Option<bool>,
+    name: Option<String>,
+    listen: Option<Vec<String>>,
+    web_bind: Option<SocketAddr>,
+    data_dir: Option<PathBuf>,
+    bootstrap: Option<Vec<String>>,
+    bootstrap_file: Option<PathBuf>,
+    topic: Option<String>,
+    channels: Vec<String>,
+    admin_token: Option<String>,
+    max_posts: Option<usize>,
+    gossipsub: GossipsubSettings,
+    rate_limits: RateLimitSettings,
+}
+
+/// Réglages du mesh Gossipsub (section `[gossipsub]` du fichier)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GossipsubSettings {
+    pub heartbeat_secs: u64,
+    pub mesh_n_low: usize,
+    pub mesh_n: usize,
+    pub mesh_n_high: usize,
+    pub mesh_outbound_min: usize,
+    pub gossip_lazy: usize,
+    pub history_length: usize,
+    pub history_gossip: usize,
+    pub duplicate_cache_secs: u64,
+}
+
+impl Default for GossipsubSettings {
+    fn default() -> Self {
+        Self {
+            heartbeat_secs: 5,
+            mesh_n_low: 2,
+            mesh_n: 3,
+            mesh_n_high: 6,
+            mesh_outbound_min: 1,
+            gossip_lazy: 3,
+            history_length: 5,
+            history_gossip: 3,
+            duplicate_cache_secs: 60,
+        }
+    }
+}
+
+/// Configuration effective du nœud
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub relay: bool,
+    pub name: Option<String>,
+    pub listen: Vec<Multiaddr>,
+    pub web_bind: SocketAddr,
+    pub data_dir: PathBuf,
+    pub bootstrap: Vec<Multiaddr>,
+    pub bootstrap_file: PathBuf,
+    pub topic: String,
+    /// Canaux rejoints au démarrage et jamais quittés
+    pub channels: Vec<String>,
+    /// Sans jeton, l'API de modération et la signature par le relais sont désactivées
+    pub admin_token: Option<String>,
+    /// Posts conservés, les plus anciens supprimés en premier ; borne aussi les tombstones
+    pub max_posts: usize,
+    pub gossipsub: GossipsubSettings,
+    pub rate_limits: RateLimitSettings,
+}
+
+impl Config {
+    /// Lit la ligne de commande, l'environnement et le fichier de configuration
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let cli = Cli::parse();
+
+        let file = match &cli.config {
+            Some(path) => read_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => read_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => FileConfig::default(),
+        };
+
+        let relay = cli.relay || file.relay.unwrap_or(false);
+
+        let listen = if !cli.listen.is_empty() {
+            cli.listen
+        } else if let Some(addrs) = file.listen {
+            parse_addrs("listen", &addrs)?
+        } else {
+            let defaults = if relay { DEFAULT_RELAY_LISTEN } else { DEFAULT_CLIENT_LISTEN };
+            defaults.iter().map(|a| a.parse()).collect::<Result<_, _>>()?
+        };
+
+        let mut web_bind = cli
+            .web_bind
+            .or(file.web_bind)
+            .unwrap_or_else(|| DEFAULT_WEB_BIND.parse().expect("adresse web par défaut valide"));
+        if let Some(port) = cli.web_port {
+            web_bind.set_port(port);
+        }
+
+        let mut channels = file.channels;
+        channels.extend(cli.channels);
+        channels.sort();
+        channels.dedup();
+
+        let mut bootstrap = parse_addrs("bootstrap", &file.bootstrap.unwrap_or_default())?;
+        bootstrap.extend(cli.bootstrap);
+
+        let config = Self {
+            relay,
+            name: cli.name.or(file.name),
+            listen,
+            web_bind,
+            data_dir: cli.data_dir.or(file.data_dir).unwrap_or_else(|| PathBuf::from(".")),
+            bootstrap,
+            bootstrap_file: cli
+                .bootstrap_file
+                .or(file.bootstrap_file)
+                .unwrap_or_else(|| PathBuf::from("bootstrap.txt")),
+            topic: cli.topic.or(file.topic).unwrap_or_else(|| DEFAULT_TOPIC.to_string()),
+            channels,
+            admin_token: cli.admin_token.or(file.admin_token),
+            max_posts: cli.max_posts.or(file.max_posts).unwrap_or(DEFAULT_MAX_POSTS),
+            gossipsub: file.gossipsub,
+            rate_limits: file.rate_limits,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.topic.trim().is_empty() {
+            return Err("topic ne peut pas être vide".into());
+        }
+        if let Some(channel) = self.channels.iter().find(|c| !channels::is_valid_name(c)) {
+            return Err(format!("channels: {}: {}", channel, channels::ChannelError::InvalidName));
+        }
+        if self.listen.is_empty() {
+            return Err("au moins une adresse listen est requise".into());
+        }
+        if let Some(name) = &self.name {
+            if name.trim().is_empty() {
+                return Err("name ne peut pas être vide".into());
+            }
+        }
+        if let Some(token) = &self.admin_token {
+            if token.len() < 16 {
+                return Err("admin_token doit faire au moins 16 caractères".into());
+            }
+        }
+        if self.max_posts == 0 {
+            return Err("max_posts doit être > 0".into());
+        }
+        let g = &self.gossipsub;
+        if g.heartbeat_secs == 0 {
+            return Err("gossipsub.heartbeat_secs doit être > 0".into());
+        }
+        if !(g.mesh_n_low <= g.mesh_n && g.mesh_n <= g.mesh_n_high) {
+            return Err(format!(
+                "gossipsub: il faut mesh_n_low ({}) <= mesh_n ({}) <= mesh_n_high ({})",
+                g.mesh_n_low, g.mesh_n, g.mesh_n_high
+            ));
+        }
+        if g.mesh_outbound_min > g.mesh_n / 2 {
+            return Err(format!(
+                "gossipsub: mesh_outbound_min ({}) doit être <= mesh_n / 2 ({})",
+                g.mesh_outbound_min,
+                g.mesh_n / 2
+            ));
+        }
+        if g.history_gossip > g.history_length {
+            return Err(format!(
+                "gossipsub: history_gossip ({}) doit être <= history_length ({})",
+                g.history_gossip, g.history_length
+            ));
+        }
+        let limits = &self.rate_limits;
+        for (scope, limit) in [
+            ("session", limits.session),
+            ("ip", limits.ip),
+            ("author", limits.author),
+            ("peer", limits.peer),
+            ("reaction", limits.reaction),
+            ("follow", limits.follow),
+            ("profile", limits.profile),
+            ("dm", limits.dm),
+            ("edit", limits.edit),
+            ("channel", limits.channel),
+            ("media", limits.media),
+            ("fetch", limits.fetch),
+        ] {
+            if limit.burst == 0 {
+                return Err(format!("rate_limits.{}.burst doit être > 0", scope));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn identity_path(&self) -> PathBuf {
+        self.data_dir.join("identity.key")
+    }
+
+    pub fn store_path(&self) -> PathBuf {
+        self.data_dir.join("zeta-data")
+    }
+
+    pub fn media_path(&self) -> PathBuf {
+        self.data_dir.join("media")
+    }
+
+    pub fn moderation_path(&self) -> PathBuf {
+        self.data_dir.join("moderation.json")
+    }
+}
+
+fn read_file(path: &Path) -> Result<FileConfig, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("lecture de {} impossible: {}", path.display(), e))?;
+    let file = toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(file)
+}
+
+fn parse_addrs(field: &str, addrs: &[String]) -> Result<Vec<Multiaddr>, String> {
+    addrs
+        .iter()
+        .map(|a| a.parse().map_err(|e| format!("{}: adresse invalide '{}': {}", field, a, e)))
+        .collect()
+}
@@ -0,0 +1,119 @@
+//! Vivacité et disponibilité du relais
+//!
+//! `/healthz` répond tant que le serveur web tourne. `/readyz` dit si le relais
+//! participe vraiment au réseau : la boucle principale relève à chaque tick le
+//! nombre de peers du mesh de son topic, et lance hors de la boucle un test
+//! d'écriture du stockage. Un relevé trop ancien signifie que la boucle est
+//! bloquée ; un test d'écriture qui ne se termine pas, que le disque l'est.
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Période des relevés de la boucle principale
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Au-delà, la boucle principale (ou le test d'écriture) est considérée comme bloquée
+pub const STALL_AFTER: Duration = Duration::from_secs(30);
+
+/// Dernier relevé de la boucle principale
+struct Sample {
+    at: Instant,
+    mesh_peers: usize,
+}
+
+/// Tests d'écriture du stockage
+#[derive(Default)]
+struct Probe {
+    /// Début du test en cours
+    running: Option<Instant>,
+    last: Option<Result<(), String>>,
+}
+
+pub struct Health {
+    started: Instant,
+    last: Mutex<Option<Sample>>,
+    probe: Mutex<Probe>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Corps de `/readyz`
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub mesh: Check,
+    pub storage: Check,
+    pub swarm_loop: Check,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self { started: Instant::now(), last: Mutex::new(None), probe: Mutex::new(Probe::default()) }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Relevé fait par la boucle principale
+    pub fn record(&self, mesh_peers: usize) {
+        *self.last.lock().unwrap() = Some(Sample { at: Instant::now(), mesh_peers });
+    }
+
+    /// Réserve le prochain test d'écriture ; `false` si le précédent n'est pas terminé
+    pub fn start_probe(&self) -> bool {
+        let mut probe = self.probe.lock().unwrap();
+        if probe.running.is_some() {
+            return false;
+        }
+        probe.running = Some(Instant::now());
+        true
+    }
+
+    pub fn finish_probe(&self, storage: Result<(), String>) {
+        let mut probe = self.probe.lock().unwrap();
+        probe.running = None;
+        probe.last = Some(storage);
+    }
+
+    pub fn readiness(&self) -> Readiness {
+        let storage = self.storage_check();
+        let last = self.last.lock().unwrap();
+        let Some(sample) = last.as_ref() else {
+            let pending = || Check { ok: false, detail: "aucun relevé de la boucle principale".to_string() };
+            return Readiness { ready: false, mesh: pending(), storage, swarm_loop: pending() };
+        };
+
+        let age = sample.at.elapsed();
+        let swarm_loop = Check {
+            ok: age < STALL_AFTER,
+            detail: format!("dernier relevé il y a {}s", age.as_secs()),
+        };
+        let mesh = Check {
+            ok: sample.mesh_peers > 0,
+            detail: format!("{} peer(s) dans le mesh", sample.mesh_peers),
+        };
+        Readiness { ready: swarm_loop.ok && mesh.ok && storage.ok, mesh, storage, swarm_loop }
+    }
+
+    fn storage_check(&self) -> Check {
+        let probe = self.probe.lock().unwrap();
+        if let Some(since) = probe.running.filter(|since| since.elapsed() >= STALL_AFTER) {
+            return Check { ok: false, detail: format!("écriture en attente depuis {}s", since.elapsed().as_secs()) };
+        }
+        match &probe.last {
+            Some(Ok(())) => Check { ok: true, detail: "écriture possible".to_string() },
+            Some(Err(e)) => Check { ok: false, detail: format!("écriture impossible: {}", e) },
+            None => Check { ok: false, detail: "aucun test d'écriture terminé".to_string() },
+        }
+    }
+}
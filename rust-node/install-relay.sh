@@ -92,7 +92,8 @@ SERVICEEOF
 if command -v ufw &>/dev/null; then
     ufw allow 3030/tcp &>/dev/null || true
     ufw allow 4001/tcp &>/dev/null || true
-    echo "   ✅ Ports ouverts (3030, 4001)"
+    ufw allow 4002/tcp &>/dev/null || true
+    echo "   ✅ Ports ouverts (3030, 4001, 4002)"
 fi
 
 # === DÉMARRER ===
@@ -114,6 +115,7 @@ echo "════════════════════════
 echo ""
 echo "🌍 Interface web : http://$IP:3030"
 echo "🔗 P2P port      : $IP:4001"
+echo "🧩 P2P WebSocket : $IP:4002 (navigateurs js-libp2p)"
 echo ""
 echo "📋 Commandes utiles :"
 echo "   sudo systemctl status zeta-relay"
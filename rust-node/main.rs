@@ -1,53 +1,231 @@
 //! Zeta Network - Réseau social P2P décentralisé
 //! 
-//! Architecture : libp2p 0.51 + Gossipsub + mDNS + TCP + Yamux
+//! Architecture : libp2p 0.51 + Gossipsub + mDNS + Kademlia + Identify + TCP/WebSocket + Yamux
 
 use libp2p::{
-    core::upgrade,
-    gossipsub::{self, IdentTopic, MessageAuthenticity},
+    core::{upgrade, ConnectedPoint},
+    gossipsub::{self, IdentTopic, MessageAcceptance, MessageAuthenticity},
+    identity::ed25519,
+    identify,
+    kad::{store::MemoryStore, Kademlia, KademliaConfig, KademliaEvent},
     mdns,
+    multiaddr::Protocol,
+    request_response,
     noise, yamux,
-    swarm::{SwarmBuilder, SwarmEvent},
+    swarm::{AddressScore, SwarmBuilder, SwarmEvent},
     tcp::tokio::Transport as TokioTcpTransport,
+    websocket::WsConfig,
     Multiaddr, PeerId, Transport,
 };
 use libp2p::swarm::NetworkBehaviour;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
+use channels::Channels;
+use health::Health;
+use media::{Attachment, MediaStore};
+use metrics::{Metrics, Source};
+use config::Config;
+use protocol::DecodeStats;
+use moderation::Moderation;
+use rate_limit::{Kind, RateLimits};
+use search::{SearchIndex, SearchResult, SearchResults};
+use thread::{ThreadNode, TimelinePage, TimelinePost};
+use validation::Rejection;
+use serde_json::Value;
+use storage::{Conversation, Cursor, PostStore, SledStore};
 use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{error, info, warn};
 
+mod channels;
+mod codec;
+mod config;
+mod health;
+mod media;
+mod metrics;
+mod moderation;
+mod protocol;
+mod rate_limit;
+mod scoring;
+mod search;
+mod signing;
+mod storage;
+mod sync;
+mod thread;
+mod validation;
 mod web_server;
 
-const TOPIC: &str = "zeta2-social";
+/// DHT privée au réseau Zeta, séparée de celle d'IPFS
+const KAD_PROTOCOL: &str = "/zeta/kad/1.0.0";
+const IDENTIFY_PROTOCOL: &str = "/zeta/id/1.0.0";
+/// Au-delà, les peers découverts par Kademlia restent dans la table de routage sans être dialés
+const MAX_DIALED_PEERS: usize = 25;
+/// Nombre de posts servis par `/api/network`
+pub const TIMELINE_LIMIT: usize = 1000;
+/// Taille par défaut d'une page de timeline (`/api/posts`, `init`, `load_more`)
+pub const PAGE_LIMIT: usize = 50;
+/// Messages privés remis en une fois à la connexion, ou servis par conversation
+const MAX_PENDING_DMS: usize = 500;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Post {
     pub id: String,
+    /// Clé publique Ed25519 de l'auteur (hex)
     pub author: String,
     pub author_name: String,
     pub content: String,
     pub timestamp: i64,
+    /// Post auquel celui-ci répond
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Premier post du fil de discussion, renseigné avec `reply_to`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Canal du post ; publié sur le topic du canal plutôt que sur le topic principal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Pièces jointes, servies par `/media/{hash}`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// Signature Ed25519 de `signing::canonical_bytes` (hex)
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// Réaction d'un auteur à un post ; une seule par auteur et par post, la plus récente l'emporte
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reaction {
+    pub post_id: String,
+    /// Clé publique Ed25519 de l'auteur (hex)
+    pub author: String,
+    /// Emoji ou type de réaction (`like`, `❤️`...)
+    pub reaction: String,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::reaction_bytes` (hex)
+    pub signature: String,
+}
+
+/// Nouvelle version du contenu d'un post, signée par son auteur
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Edit {
+    pub post_id: String,
+    /// Doit être l'auteur du post modifié
+    pub author: String,
+    pub content: String,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::edit_bytes` (hex)
+    pub signature: String,
+}
+
+/// Retrait d'un post par son auteur ; conservé comme tombstone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delete {
+    pub post_id: String,
+    /// Doit être l'auteur du post supprimé
+    pub author: String,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::delete_bytes` (hex)
+    pub signature: String,
+}
+
+/// Profil public d'un auteur ; seul le plus récent est conservé
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Clé publique Ed25519 de l'auteur (hex)
+    pub author: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub bio: String,
+    /// URL de l'avatar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    /// Clé publique X25519 (hex) à laquelle chiffrer les messages privés
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dm_key: Option<String>,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::profile_bytes` (hex)
+    pub signature: String,
+}
+
+/// Abonnement (message `Follow`) ou désabonnement (`Unfollow`) d'un auteur à
+/// un autre ; le plus récent l'emporte pour chaque paire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Follow {
+    /// Clé publique Ed25519 de l'abonné (hex)
+    pub author: String,
+    /// Clé publique de l'auteur suivi
+    pub target: String,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::follow_bytes` (hex)
+    pub signature: String,
+}
+
+/// Message privé chiffré de bout en bout par le navigateur de l'expéditeur.
+///
+/// Les relais ne voient que les métadonnées (expéditeur, destinataire, date) :
+/// `ciphertext` est un AES-256-GCM dont la clé est dérivée par HKDF-SHA256
+/// (info `zeta-dm-v1`) d'un échange X25519 entre `sender_dm_key` et
+/// `recipient_dm_key`, les clés publiées dans les profils signés.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessage {
+    pub id: String,
+    /// Clé publique Ed25519 de l'expéditeur (hex)
+    pub sender: String,
+    /// Clé publique Ed25519 du destinataire (hex)
+    pub recipient: String,
+    /// Clés X25519 (hex) utilisées pour l'échange
+    pub sender_dm_key: String,
+    pub recipient_dm_key: String,
+    /// Nonce AES-GCM de 12 octets (hex)
+    pub nonce: String,
+    /// Contenu chiffré, tag inclus (hex)
+    pub ciphertext: String,
+    pub timestamp: i64,
+    /// Signature Ed25519 de `signing::direct_message_bytes` (hex)
+    pub signature: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NetworkMessage {
     Post(Post),
     Heartbeat { peer_id: String },
+    Reaction(Reaction),
+    Edit(Edit),
+    Delete(Delete),
+    Profile(Profile),
+    Follow(Follow),
+    Unfollow(Follow),
+    DirectMessage(DirectMessage),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PeerInfo {
     pub peer_id: String,
+    /// Adresse distante de la connexion
     pub address: String,
     pub name: Option<String>,
     pub is_browser: bool,
+    /// Adresses d'écoute annoncées via Identify
+    #[serde(default)]
+    pub listen_addrs: Vec<String>,
+    #[serde(default)]
+    pub agent_version: Option<String>,
+    #[serde(default)]
+    pub protocol_version: Option<String>,
+    /// Protocoles libp2p supportés par le peer
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// Score Gossipsub, rafraîchi périodiquement (absent pour les navigateurs du pont `/ws`)
+    #[serde(default)]
+    pub score: Option<f64>,
+    /// Score sous `scoring::GRAYLIST_THRESHOLD` : ses messages sont ignorés
+    #[serde(default)]
+    pub graylisted: bool,
 }
 
 /// Comportement réseau combiné
@@ -56,12 +234,20 @@ pub struct PeerInfo {
 struct ZetaBehaviour {
     gossipsub: gossipsub::Behaviour,
     mdns: mdns::tokio::Behaviour,
+    identify: identify::Behaviour,
+    kademlia: Kademlia<MemoryStore>,
+    sync: sync::Behaviour,
+    media: media::Behaviour,
 }
 
 #[derive(Debug)]
 enum ZetaEvent {
     Gossipsub(gossipsub::Event),
     Mdns(mdns::Event),
+    Identify(Box<identify::Event>),
+    Kademlia(KademliaEvent),
+    Sync(sync::Event),
+    Media(media::Event),
 }
 
 impl From<gossipsub::Event> for ZetaEvent {
@@ -76,24 +262,91 @@ impl From<mdns::Event> for ZetaEvent {
     }
 }
 
+impl From<identify::Event> for ZetaEvent {
+    fn from(event: identify::Event) -> Self {
+        ZetaEvent::Identify(Box::new(event))
+    }
+}
+
+impl From<KademliaEvent> for ZetaEvent {
+    fn from(event: KademliaEvent) -> Self {
+        ZetaEvent::Kademlia(event)
+    }
+}
+
+impl From<sync::Event> for ZetaEvent {
+    fn from(event: sync::Event) -> Self {
+        ZetaEvent::Sync(event)
+    }
+}
+
+impl From<media::Event> for ZetaEvent {
+    fn from(event: media::Event) -> Self {
+        ZetaEvent::Media(event)
+    }
+}
+
 #[derive(Clone)]
 pub struct NetworkState {
     pub peers: Arc<RwLock<HashMap<String, PeerInfo>>>,
-    pub posts: Arc<RwLock<Vec<Post>>>,
+    pub store: Arc<dyn PostStore>,
     pub local_peer_id: PeerId,
     pub local_name: String,
+    /// Adresses d'écoute complètes (`.../p2p/<peer id>`), dont `/ws` pour les navigateurs
+    pub listen_addrs: Arc<RwLock<Vec<String>>>,
+    /// Clé du nœud, utilisée pour signer les posts créés via `/api/post`
+    pub author_key: ed25519::Keypair,
+    /// Messages Gossipsub impossibles à décoder, par cause
+    pub decode_stats: Arc<DecodeStats>,
+    pub rate_limits: Arc<RateLimits>,
+    /// Règles de modération du relais, appliquées au stockage, à l'API et au relais Gossipsub
+    pub moderation: Arc<Moderation>,
+    /// Canaux auxquels le relais est abonné
+    pub channels: Arc<Channels>,
+    /// Index plein texte des posts stockés
+    pub search: Arc<SearchIndex>,
+    /// Blobs des pièces jointes
+    pub media: Arc<MediaStore>,
+    /// Compteurs et jauges exposés par `/metrics`
+    pub metrics: Arc<Metrics>,
+    /// Relevés de la boucle principale servis par `/readyz`
+    pub health: Arc<Health>,
     pub ws_broadcast: broadcast::Sender<String>,
+    /// Messages privés, remis par le WebSocket aux seuls participants
+    pub dm_broadcast: broadcast::Sender<DirectMessage>,
 }
 
 impl NetworkState {
-    fn new(local_peer_id: PeerId, local_name: String) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        local_peer_id: PeerId,
+        local_name: String,
+        author_key: ed25519::Keypair,
+        store: Arc<dyn PostStore>,
+        rate_limits: RateLimits,
+        moderation: Moderation,
+        channels: Channels,
+        media: MediaStore,
+    ) -> Self {
         let (ws_broadcast, _) = broadcast::channel(100);
+        let (dm_broadcast, _) = broadcast::channel(100);
         Self {
             peers: Arc::new(RwLock::new(HashMap::new())),
-            posts: Arc::new(RwLock::new(Vec::new())),
+            store,
             local_peer_id,
             local_name,
+            listen_addrs: Arc::new(RwLock::new(Vec::new())),
+            author_key,
+            decode_stats: Arc::new(DecodeStats::default()),
+            rate_limits: Arc::new(rate_limits),
+            moderation: Arc::new(moderation),
+            channels: Arc::new(channels),
+            search: Arc::new(SearchIndex::new()),
+            media: Arc::new(media),
+            metrics: Arc::new(Metrics::new()),
+            health: Arc::new(Health::new()),
             ws_broadcast,
+            dm_broadcast,
         }
     }
 
@@ -104,6 +357,42 @@ impl NetworkState {
         let _ = self.ws_broadcast.send(msg.to_string());
     }
 
+    /// Complète un peer connu avec les informations reçues via Identify
+    pub async fn update_peer_identity(&self, peer_id: &str, info: &identify::Info) {
+        let mut peers = self.peers.write().await;
+        let Some(peer) = peers.get_mut(peer_id) else {
+            return;
+        };
+        peer.listen_addrs = info.listen_addrs.iter().map(|a| a.to_string()).collect();
+        peer.agent_version = Some(info.agent_version.clone());
+        peer.protocol_version = Some(info.protocol_version.clone());
+        peer.protocols = info.protocols.clone();
+        peer.is_browser = is_browser_agent(&info.agent_version);
+        if let Some(name) = name_from_agent_version(&info.agent_version) {
+            peer.name = Some(name);
+        }
+        let msg = serde_json::json!({"type": "peer_updated", "peer": peer});
+        let _ = self.ws_broadcast.send(msg.to_string());
+    }
+
+    /// Met à jour le score Gossipsub des peers libp2p connus
+    pub async fn update_peer_scores(&self, scores: &HashMap<String, f64>) {
+        let mut peers = self.peers.write().await;
+        for (peer_id, &score) in scores {
+            let Some(peer) = peers.get_mut(peer_id) else {
+                continue;
+            };
+            let graylisted = score < scoring::GRAYLIST_THRESHOLD;
+            if graylisted && !peer.graylisted {
+                warn!("⛔ Peer {} graylisté (score {:.1})", peer_id, score);
+            } else if !graylisted && peer.graylisted {
+                info!("✅ Peer {} sorti de la graylist (score {:.1})", peer_id, score);
+            }
+            peer.score = Some(score);
+            peer.graylisted = graylisted;
+        }
+    }
+
     pub async fn remove_peer(&self, peer_id: &str) {
         self.peers.write().await.remove(peer_id);
         let msg = serde_json::json!({"type": "peer_left", "peer_id": peer_id});
@@ -111,102 +400,598 @@ impl NetworkState {
     }
 
     pub async fn add_post(&self, post: Post) {
-        let mut posts = self.posts.write().await;
-        if posts.iter().any(|p| p.id == post.id) {
+        if let Err(verdict) = self.moderation.check_post(&post) {
+            info!("🛡️ Post {} non stocké: {}", post.id, verdict);
             return;
         }
-        posts.insert(0, post.clone());
-        if posts.len() > 1000 {
-            posts.truncate(1000);
+        match self.store.insert(&post) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                error!("❌ Échec d'écriture du post {}: {}", post.id, e);
+                return;
+            }
+        }
+        match self.store.evict_oldest() {
+            Ok(evicted) => {
+                for old in &evicted {
+                    self.search.remove(&old.id);
+                }
+                if !evicted.is_empty() {
+                    info!("🧹 {} post(s) ancien(s) retiré(s) par la rétention", evicted.len());
+                }
+            }
+            Err(e) => error!("❌ Échec de la rétention des posts: {}", e),
+        }
+        for attachment in &post.attachments {
+            // Au-delà du budget, le blob sera demandé à la première lecture
+            if let Err(e) = self.media.prefetch(attachment) {
+                info!("🖼️ Média {} non préchargé: {}", attachment.hash, e);
+            }
+        }
+        let parent = post.reply_to.clone();
+        // Une réponse reçue avant ce post a déjà pu incrémenter son compteur
+        let timeline_post = self.timeline_post(post);
+        self.search.index(&timeline_post.post);
+        let msg = serde_json::json!({"type": "new_post", "post": timeline_post});
+        let _ = self.ws_broadcast.send(msg.to_string());
+        if let Some(parent) = parent {
+            let reply_count = self.store.reply_count(&parent).unwrap_or(0);
+            let msg = serde_json::json!({"type": "reply_count", "id": parent, "reply_count": reply_count});
+            let _ = self.ws_broadcast.send(msg.to_string());
+        }
+    }
+
+    /// Vérifie chaque pièce jointe d'un post créé ici : son blob doit être sur
+    /// ce relais pour être servi aux peers, avec le type et la taille déclarés
+    pub async fn check_attachments(&self, post: &Post) -> Result<(), String> {
+        for attachment in &post.attachments {
+            // Relit le blob entier : hors du runtime async
+            let media = self.media.clone();
+            let checked = attachment.clone();
+            tokio::task::spawn_blocking(move || media.check(&checked))
+                .await
+                .unwrap_or_else(|e| Err(media::MediaError::Io(e.into())))
+                .map_err(|e| format!("pièce jointe {}: {}", attachment.hash, e))?;
+        }
+        Ok(())
+    }
+
+    /// Retire un post du stockage et l'empêche de revenir par Gossipsub ou la sync
+    pub async fn remove_post(&self, id: &str) -> Result<bool, String> {
+        self.moderation.remove_post(id)?;
+        let removed = self.store.remove(id).map_err(|e| e.to_string())?.is_some();
+        self.search.remove(id);
+        let msg = serde_json::json!({"type": "post_removed", "id": id});
+        let _ = self.ws_broadcast.send(msg.to_string());
+        Ok(removed)
+    }
+
+    /// Les posts les plus récents, du plus récent au plus ancien ; les posts
+    /// stockés avant une nouvelle règle de modération sont masqués
+    pub fn recent_posts(&self, limit: usize) -> Vec<Post> {
+        let mut posts = self.store.latest(limit).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        });
+        posts.retain(|post| self.is_visible(post));
+        posts
+    }
+
+    fn timeline_post(&self, mut post: Post) -> TimelinePost {
+        let reply_count = self.store.reply_count(&post.id).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            0
+        });
+        let edited_at = self.author_edits(&post).last().map(|edit| {
+            post.content = edit.content.clone();
+            edit.timestamp
+        });
+        // Le nom signé dans le post n'est qu'une valeur par défaut : le profil de la clé l'emporte
+        let author_avatar = self.profile(&post.author).and_then(|profile| {
+            post.author_name = profile.display_name;
+            profile.avatar
+        });
+        TimelinePost {
+            reactions: self.reaction_counts(&post.id),
+            post,
+            reply_count,
+            edited_at,
+            author_avatar,
+        }
+    }
+
+    /// Profil le plus récent d'un auteur, s'il n'est pas bloqué
+    pub fn profile(&self, author: &str) -> Option<Profile> {
+        let profile = self.store.profile(author).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            None
+        })?;
+        self.moderation.check_profile(&profile).is_ok().then_some(profile)
+    }
+
+    /// Enregistre un profil s'il est plus récent que celui connu ;
+    /// renvoie `false` s'il n'a pas été retenu
+    pub async fn set_profile(&self, profile: Profile) -> bool {
+        if let Err(verdict) = self.moderation.check_profile(&profile) {
+            info!("🛡️ Profil de {} non stocké: {}", profile.author, verdict);
+            return false;
+        }
+        match self.store.set_profile(&profile) {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                error!("❌ Échec d'écriture du profil de {}: {}", profile.author, e);
+                return false;
+            }
+        }
+        info!("👤 Profil de {} mis à jour: {}", profile.author, profile.display_name);
+        let msg = serde_json::json!({"type": "profile", "profile": profile});
+        let _ = self.ws_broadcast.send(msg.to_string());
+        true
+    }
+
+    /// Le post et sa dernière modification passent les règles de modération actuelles
+    fn is_visible(&self, post: &Post) -> bool {
+        self.moderation.is_allowed(post)
+            && self
+                .author_edits(post)
+                .last()
+                .is_none_or(|edit| self.moderation.check_edit(edit).is_ok())
+    }
+
+    /// Modifications signées par l'auteur du post, de la plus ancienne à la plus récente
+    fn author_edits(&self, post: &Post) -> Vec<Edit> {
+        let mut edits = self.store.edits(&post.id).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        });
+        edits.retain(|edit| edit.author == post.author);
+        edits
+    }
+
+    /// Refuse une réaction à un post que ce relais ne stocke pas
+    pub fn known_post(&self, post_id: &str) -> Result<(), Rejection> {
+        match self.store.get(post_id) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(Rejection::UnknownPost),
+            Err(e) => {
+                error!("❌ Lecture du stockage impossible: {}", e);
+                Err(Rejection::UnknownPost)
+            }
+        }
+    }
+
+    /// Post stocké, à condition que `author` en soit l'auteur ; `None` s'il n'est pas encore arrivé
+    fn authored_post(&self, post_id: &str, author: &str) -> Result<Option<Post>, Rejection> {
+        match self.store.get(post_id) {
+            Ok(Some(post)) if post.author != author => Err(Rejection::NotAuthor),
+            Ok(post) => Ok(post),
+            Err(e) => {
+                error!("❌ Lecture du stockage impossible: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn is_deleted(&self, post_id: &str, author: &str) -> bool {
+        matches!(self.store.tombstone(post_id, author), Ok(Some(_)))
+    }
+
+    /// Applique une modification signée par l'auteur. Si le post n'est pas
+    /// encore arrivé, elle est gardée et appliquée à son arrivée.
+    /// Renvoie `false` si elle était déjà connue.
+    pub async fn apply_edit(&self, edit: Edit) -> Result<bool, Rejection> {
+        let post = self.authored_post(&edit.post_id, &edit.author)?;
+        if self.is_deleted(&edit.post_id, &edit.author) {
+            return Err(Rejection::Deleted);
+        }
+        let post = post.ok_or(Rejection::UnknownPost)?;
+        self.moderation.check_edit(&edit).map_err(Rejection::Moderated)?;
+        match self.store.add_edit(&edit) {
+            Ok(true) => {}
+            Ok(false) => return Ok(false),
+            Err(e) => {
+                error!("❌ Échec d'écriture de la modification de {}: {}", edit.post_id, e);
+                return Ok(false);
+            }
+        }
+        info!("✏️ Post {} modifié par son auteur", edit.post_id);
+        // Les modifications peuvent arriver dans le désordre : on réindexe la plus récente
+        let timeline_post = self.timeline_post(post);
+        self.search.index(&timeline_post.post);
+        let msg = serde_json::json!({"type": "post_edited", "post": timeline_post});
+        let _ = self.ws_broadcast.send(msg.to_string());
+        Ok(true)
+    }
+
+    /// Supprime un post à la demande de son auteur et garde une tombstone pour
+    /// refuser les copies tardives. Renvoie `false` si la suppression était déjà connue.
+    pub async fn apply_delete(&self, delete: Delete) -> Result<bool, Rejection> {
+        self.authored_post(&delete.post_id, &delete.author)?;
+        if self.is_deleted(&delete.post_id, &delete.author) {
+            return Ok(false);
+        }
+        let removed = match self.store.delete(&delete) {
+            Ok(removed) => removed,
+            Err(e) => {
+                error!("❌ Échec de la suppression de {}: {}", delete.post_id, e);
+                return Ok(false);
+            }
+        };
+        info!("🗑️ Post {} supprimé par son auteur", delete.post_id);
+        if let Some(post) = removed {
+            self.search.remove(&post.id);
+            let msg = serde_json::json!({"type": "post_deleted", "id": post.id});
+            let _ = self.ws_broadcast.send(msg.to_string());
+            if let Some(parent) = post.reply_to {
+                let reply_count = self.store.reply_count(&parent).unwrap_or(0);
+                let msg = serde_json::json!({"type": "reply_count", "id": parent, "reply_count": reply_count});
+                let _ = self.ws_broadcast.send(msg.to_string());
+            }
+        }
+        Ok(true)
+    }
+
+    /// Post original signé et modifications de son auteur ; `None` si le post est inconnu
+    pub fn history(&self, id: &str) -> Option<Value> {
+        let post = self.store.get(id).ok().flatten()?;
+        if !self.is_visible(&post) {
+            return None;
+        }
+        let mut edits = self.author_edits(&post);
+        edits.retain(|edit| self.moderation.check_edit(edit).is_ok());
+        Some(serde_json::json!({"post": post, "edits": edits}))
+    }
+
+    pub fn reaction_counts(&self, post_id: &str) -> BTreeMap<String, u64> {
+        self.store.reaction_counts(post_id).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            BTreeMap::new()
+        })
+    }
+
+    /// Enregistre une réaction et diffuse les nouveaux totaux du post ;
+    /// renvoie `false` si elle est refusée ou remplacée par une plus récente
+    pub async fn add_reaction(&self, reaction: Reaction) -> bool {
+        if let Err(verdict) = self.moderation.check_reaction(&reaction) {
+            info!("🛡️ Réaction à {} non stockée: {}", reaction.post_id, verdict);
+            return false;
+        }
+        match self.store.set_reaction(&reaction) {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                error!("❌ Échec d'écriture de la réaction à {}: {}", reaction.post_id, e);
+                return false;
+            }
+        }
+        let msg = serde_json::json!({
+            "type": "reaction",
+            "post_id": reaction.post_id,
+            "reactions": self.reaction_counts(&reaction.post_id),
+            "reaction": reaction,
+        });
+        let _ = self.ws_broadcast.send(msg.to_string());
+        true
+    }
+
+    /// Indexe tous les posts stockés, dans leur version modifiée
+    pub fn rebuild_search_index(&self) -> Result<usize, storage::StorageError> {
+        let mut cursor: Option<(i64, String)> = None;
+        loop {
+            let page = match &cursor {
+                Some((timestamp, id)) => self.store.after(*timestamp, Some(id), TIMELINE_LIMIT)?,
+                None => self.store.after(i64::MIN, None, TIMELINE_LIMIT)?,
+            };
+            let Some(last) = page.last() else {
+                return Ok(self.search.len());
+            };
+            cursor = Some((last.timestamp, last.id.clone()));
+            for post in page {
+                if let Some(edit) = self.author_edits(&post).pop() {
+                    self.search.index(&Post { content: edit.content, ..post });
+                } else {
+                    self.search.index(&post);
+                }
+            }
+        }
+    }
+
+    /// Les `limit` posts les plus pertinents pour la requête ; les posts
+    /// masqués par la modération sont écartés
+    pub fn search(&self, query: &search::Query, limit: usize) -> SearchResults {
+        let hits = self.search.search(query);
+        let total = hits.len();
+        let results = hits
+            .into_iter()
+            .filter_map(|hit| {
+                let post = self.store.get(&hit.id).ok().flatten()?;
+                self.is_visible(&post)
+                    .then(|| SearchResult { score: hit.score, post: self.timeline_post(post) })
+            })
+            .take(limit)
+            .collect();
+        SearchResults { total, results }
+    }
+
+    /// Timeline servie par `/api/network` et le message `init`
+    pub fn timeline(&self, limit: usize) -> Vec<TimelinePost> {
+        self.recent_posts(limit)
+            .into_iter()
+            .map(|post| self.timeline_post(post))
+            .collect()
+    }
+
+    /// Page de la timeline globale, ou d'un auteur ; les curseurs suivent les
+    /// posts stockés, y compris ceux masqués par la modération
+    pub fn page(&self, author: Option<&str>, before: Option<&Cursor>, after: Option<&Cursor>, limit: usize) -> TimelinePage {
+        let posts = self.store.page(author, before, after, limit).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        });
+        // En remontant le temps, une page incomplète est la dernière
+        let backward = before.is_some() || after.is_none();
+        let older = posts.last().filter(|_| !backward || posts.len() == limit).map(Cursor::of);
+        let newer = posts.first().map(Cursor::of).or_else(|| after.cloned());
+        TimelinePage {
+            posts: posts
+                .into_iter()
+                .filter(|post| self.is_visible(post))
+                .map(|post| self.timeline_post(post))
+                .collect(),
+            before: older,
+            after: newer,
         }
-        let msg = serde_json::json!({"type": "new_post", "post": post});
+    }
+
+    /// Posts d'un canal, du plus récent au plus ancien
+    pub fn channel_timeline(&self, channel: &str, limit: usize) -> Vec<TimelinePost> {
+        let mut posts = self.store.by_channel(channel, limit).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        });
+        posts.retain(|post| self.is_visible(post));
+        posts.into_iter().map(|post| self.timeline_post(post)).collect()
+    }
+
+    /// Stocke un message privé pour son destinataire ; renvoie `false` s'il
+    /// était déjà connu ou a été refusé
+    pub async fn add_direct_message(&self, dm: DirectMessage) -> bool {
+        if let Err(verdict) = self.moderation.check_direct_message(&dm) {
+            info!("🛡️ Message privé {} non stocké: {}", dm.id, verdict);
+            return false;
+        }
+        match self.store.insert_dm(&dm) {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                error!("❌ Échec d'écriture du message privé {}: {}", dm.id, e);
+                return false;
+            }
+        }
+        info!("✉️ Message privé {} pour {}", dm.id, dm.recipient);
+        let _ = self.dm_broadcast.send(dm);
+        true
+    }
+
+    /// Messages pas encore remis à `recipient`
+    pub fn pending_dms(&self, recipient: &str) -> Vec<DirectMessage> {
+        self.store.pending_dms(recipient, MAX_PENDING_DMS).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        })
+    }
+
+    pub fn mark_delivered(&self, dm: &DirectMessage) {
+        if let Err(e) = self.store.mark_delivered(dm) {
+            error!("❌ Impossible de marquer le message {} comme livré: {}", dm.id, e);
+        }
+    }
+
+    pub fn conversations(&self, author: &str) -> Vec<Conversation> {
+        self.store.conversations(author).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        })
+    }
+
+    pub fn conversation(&self, author: &str, peer: &str) -> Vec<DirectMessage> {
+        self.store.conversation(author, peer, MAX_PENDING_DMS).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Auteurs suivis par `author`
+    pub fn following(&self, author: &str) -> Vec<String> {
+        self.store.following(author).unwrap_or_else(|e| {
+            error!("❌ Lecture du stockage impossible: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Enregistre un abonnement ou un désabonnement s'il est plus récent que
+    /// le dernier connu pour la même paire
+    pub async fn set_follow(&self, follow: Follow, following: bool) -> bool {
+        if let Err(verdict) = self.moderation.check_follow(&follow) {
+            info!("🛡️ Abonnement de {} non stocké: {}", follow.author, verdict);
+            return false;
+        }
+        match self.store.set_follow(&follow, following) {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                error!("❌ Échec d'écriture de l'abonnement de {}: {}", follow.author, e);
+                return false;
+            }
+        }
+        let msg = serde_json::json!({
+            "type": "follow",
+            "author": follow.author,
+            "target": follow.target,
+            "following": following,
+        });
         let _ = self.ws_broadcast.send(msg.to_string());
+        true
+    }
+
+    /// Fil personnel : posts de `author` et des auteurs qu'il suit, du plus récent au plus ancien
+    pub fn feed(&self, author: &str, limit: usize) -> Vec<TimelinePost> {
+        let mut authors = self.following(author);
+        authors.push(author.to_string());
+        authors.sort();
+        authors.dedup();
+        let mut posts = Vec::new();
+        for author in &authors {
+            match self.store.by_author(author, limit) {
+                Ok(by_author) => posts.extend(by_author),
+                Err(e) => error!("❌ Lecture du stockage impossible: {}", e),
+            }
+        }
+        posts.retain(|post| self.is_visible(post));
+        posts.sort_by_key(|post| std::cmp::Reverse(post.timestamp));
+        posts.truncate(limit);
+        posts.into_iter().map(|post| self.timeline_post(post)).collect()
+    }
+
+    /// Un post et l'arbre de ses réponses ; `None` si le post est inconnu
+    pub fn thread(&self, id: &str) -> Option<ThreadNode> {
+        let head = match self.store.get(id) {
+            Ok(Some(post)) if self.is_visible(&post) => post,
+            Ok(_) => return None,
+            Err(e) => {
+                error!("❌ Lecture du stockage impossible: {}", e);
+                return None;
+            }
+        };
+        let root = head.root.clone().unwrap_or_else(|| head.id.clone());
+        let replies = self
+            .store
+            .thread(&root, thread::MAX_THREAD_POSTS)
+            .unwrap_or_else(|e| {
+                error!("❌ Lecture du stockage impossible: {}", e);
+                Vec::new()
+            })
+            .into_iter()
+            .filter(|post| post.id != head.id && self.is_visible(post))
+            .map(|post| self.timeline_post(post))
+            .collect();
+        Some(thread::build(self.timeline_post(head), replies))
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Configuration invalide: {}", e);
+            std::process::exit(2);
+        }
+    };
+
     tracing_subscriber::fmt()
         .with_env_filter("info,libp2p=warn")
         .init();
 
     info!("🚀 Démarrage de Zeta Network");
 
-    let args: Vec<String> = std::env::args().collect();
-    let is_relay = args.iter().any(|a| a == "--relay" || a == "--server");
-    
-    let username = args.iter()
-        .position(|x| x == "--name")
-        .and_then(|i| args.get(i + 1))
-        .cloned();
-    
-    let web_port: u16 = args.iter()
-        .position(|x| x == "--web-port")
-        .and_then(|i| args.get(i + 1))
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(3030);
-
+    let is_relay = config.relay;
     info!("⚙️ Mode: {}", if is_relay { "RELAY" } else { "CLIENT" });
 
-    let local_key = load_or_create_keypair("identity.key")?;
+    fs::create_dir_all(&config.data_dir)?;
+    let local_key = load_or_create_keypair(&config.identity_path())?;
     let local_peer_id = PeerId::from(local_key.public());
-    let local_name = username.unwrap_or_else(|| format!("Peer-{}", &local_peer_id.to_string()[..8]));
+    let local_name = config.name.clone().unwrap_or_else(|| format!("Peer-{}", &local_peer_id.to_string()[..8]));
 
     info!("🔑 Peer ID: {}", local_peer_id);
     info!("👤 Nom: {}", local_name);
 
-    // Transport TCP + Noise + Yamux
-    let transport = TokioTcpTransport::new(Default::default())
+    // Transport TCP (relais) + WebSocket (navigateurs js-libp2p), chacun avec Noise + Yamux
+    let transport = WsConfig::new(TokioTcpTransport::new(Default::default()))
+        .or_transport(TokioTcpTransport::new(Default::default()))
         .upgrade(upgrade::Version::V1)
         .authenticate(noise::Config::new(&local_key).expect("Noise config"))
         .multiplex(yamux::Config::default())
         .boxed();
 
     // Gossipsub - configuration optimisée pour relais distants
+    let tuning = &config.gossipsub;
     let gossipsub_config = gossipsub::ConfigBuilder::default()
-        .heartbeat_interval(Duration::from_secs(5))  // Heartbeat plus fréquent
-        .validation_mode(gossipsub::ValidationMode::Permissive)
-        .mesh_n_low(2)           // Minimum 2 peers dans le mesh
-        .mesh_n(3)               // Cible 3 peers
-        .mesh_n_high(6)          // Maximum 6 peers
-        .mesh_outbound_min(1)    // Minimum 1 connexion sortante
-        .gossip_lazy(3)          // Gossip à 3 peers
-        .history_length(5)       // Garder 5 heartbeats d'historique
-        .history_gossip(3)       // Gossip les 3 derniers
-        .duplicate_cache_time(Duration::from_secs(60))  // Cache de déduplication
+        .heartbeat_interval(Duration::from_secs(tuning.heartbeat_secs))  // Heartbeat plus fréquent
+        .validation_mode(gossipsub::ValidationMode::Strict)
+        .validate_messages()                           // Relayé seulement après report_message_validation_result
+        .max_transmit_size(validation::MAX_MESSAGE_BYTES)
+        .mesh_n_low(tuning.mesh_n_low)                 // Minimum de peers dans le mesh
+        .mesh_n(tuning.mesh_n)                         // Cible
+        .mesh_n_high(tuning.mesh_n_high)               // Maximum
+        .mesh_outbound_min(tuning.mesh_outbound_min)   // Minimum de connexions sortantes
+        .gossip_lazy(tuning.gossip_lazy)               // Nombre de peers pour le gossip
+        .history_length(tuning.history_length)         // Heartbeats d'historique gardés
+        .history_gossip(tuning.history_gossip)         // Heartbeats gossipés
+        .duplicate_cache_time(Duration::from_secs(tuning.duplicate_cache_secs))  // Cache de déduplication
         .build()
-        .expect("Config Gossipsub valide");
+        .map_err(|e| format!("Config Gossipsub invalide: {}", e))?;
 
     let mut gossipsub = gossipsub::Behaviour::new(
         MessageAuthenticity::Signed(local_key.clone()),
         gossipsub_config,
     ).expect("Gossipsub créé");
 
-    let topic = IdentTopic::new(TOPIC);
-    gossipsub.subscribe(&topic).unwrap();
-    info!("📢 Abonné au topic: {}", TOPIC);
+    let topic = IdentTopic::new(config.topic.clone());
+    let channel_topics: Vec<IdentTopic> = config
+        .channels
+        .iter()
+        .map(|channel| channels::topic(&config.topic, Some(channel)))
+        .collect();
+    let scored_topics = std::iter::once(&topic).chain(&channel_topics).map(|t| t.hash());
+    gossipsub
+        .with_peer_score(scoring::params(scored_topics), scoring::thresholds())
+        .map_err(|e| format!("Paramètres de score Gossipsub invalides: {}", e))?;
+    for subscribed in std::iter::once(&topic).chain(&channel_topics) {
+        gossipsub.subscribe(subscribed).unwrap();
+        info!("📢 Abonné au topic: {}", subscribed);
+    }
 
     // mDNS
     let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?;
 
-    let behaviour = ZetaBehaviour { gossipsub, mdns };
+    // Kademlia - table de routage entre relais, amorcée par les bootstrap
+    let mut kad_config = KademliaConfig::default();
+    kad_config.set_protocol_names(vec![KAD_PROTOCOL.as_bytes().into()]);
+    kad_config.set_query_timeout(Duration::from_secs(30));
+    let kademlia = Kademlia::with_config(local_peer_id, MemoryStore::new(local_peer_id), kad_config);
+
+    // Identify - adresses d'écoute, version et protocoles de chaque peer
+    let identify = identify::Behaviour::new(
+        identify::Config::new(IDENTIFY_PROTOCOL.to_string(), local_key.public())
+            .with_agent_version(agent_version(&local_name))
+            .with_push_listen_addr_updates(true),
+    );
+
+    let behaviour = ZetaBehaviour {
+        gossipsub,
+        mdns,
+        identify,
+        kademlia,
+        sync: sync::behaviour(),
+        media: media::behaviour(),
+    };
 
     // Swarm
     let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build();
 
     // Écouter
-    if is_relay {
-        match swarm.listen_on("/ip4/0.0.0.0/tcp/4001".parse()?) {
-            Ok(_) => info!("🖥️ Mode RELAY - Écoute sur 0.0.0.0:4001"),
+    for addr in &config.listen {
+        match swarm.listen_on(addr.clone()) {
+            Ok(_) => info!("{} Écoute sur {}", if is_relay { "🖥️ Mode RELAY -" } else { "💻 Mode CLIENT -" }, addr),
             Err(e) => {
-                error!("❌ Impossible d'écouter sur le port 4001: {}", e);
-                error!("   Le port est peut-être déjà utilisé. Vérifiez avec: sudo lsof -i :4001");
+                error!("❌ Impossible d'écouter sur {}: {}", addr, e);
+                error!("   Le port est peut-être déjà utilisé. Vérifiez avec: sudo lsof -i");
                 return Err(e.into());
             }
         }
-    } else {
-        swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
-        info!("💻 Mode CLIENT - Port aléatoire");
     }
 
     // Obtenir l'IP locale pour éviter de se connecter à soi-même
@@ -214,7 +999,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     info!("📍 IP locale détectée: {}", local_ip.as_deref().unwrap_or("inconnue"));
 
     // Bootstrap peers - connexion sans Peer ID requis
-    let bootstrap_addrs = load_bootstrap_addrs();
+    let mut bootstrap_addrs = load_bootstrap_addrs(&config.bootstrap_file);
+    bootstrap_addrs.extend(config.bootstrap.iter().cloned());
     for addr in &bootstrap_addrs {
         // Éviter de se connecter à soi-même
         let addr_str = addr.to_string();
@@ -230,7 +1016,27 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let network_state = NetworkState::new(local_peer_id, local_name.clone());
+    let store_path = config.store_path();
+    let store = SledStore::open(&store_path, config.max_posts).map_err(|e| e as Box<dyn Error>)?;
+    info!("💾 {} post(s) chargé(s) depuis {}", store.len(), store_path.display());
+
+    let moderation = Moderation::load(config.moderation_path())?;
+    let author_key = local_key.clone().try_into_ed25519()?;
+    let (channel_tx, mut channel_rx) = mpsc::unbounded_channel::<channels::Command>();
+    let (media_tx, mut media_rx) = mpsc::unbounded_channel::<media::Command>();
+    let media_store = MediaStore::open(config.media_path(), media_tx)?;
+    let network_state = NetworkState::new(
+        local_peer_id,
+        local_name.clone(),
+        author_key,
+        Arc::new(store),
+        RateLimits::new(config.rate_limits.clone()),
+        moderation,
+        Channels::new(config.channels.clone(), channel_tx),
+        media_store,
+    );
+    let indexed = network_state.rebuild_search_index().map_err(|e| e as Box<dyn Error>)?;
+    info!("🔎 {} post(s) indexé(s) pour la recherche", indexed);
     
     let (post_tx, mut post_rx) = mpsc::unbounded_channel::<Post>();
     let (ws_to_p2p_tx, mut ws_to_p2p_rx) = mpsc::unbounded_channel::<NetworkMessage>();
@@ -238,13 +1044,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Serveur web
     let web_state = network_state.clone();
     let web_name = local_name.clone();
+    let web_bind = config.web_bind;
+    let admin_token = config.admin_token.clone();
     tokio::spawn(async move {
-        if let Err(e) = web_server::start_server(web_state, post_tx, ws_to_p2p_tx, web_name, is_relay, web_port).await {
+        if let Err(e) = web_server::start_server(web_state, post_tx, ws_to_p2p_tx, web_name, is_relay, web_bind, admin_token).await {
             error!("❌ Erreur serveur web: {}", e);
         }
     });
 
-    info!("🌐 Interface web: http://localhost:{}", web_port);
+    info!("🌐 Interface web: http://{}", web_bind);
     info!("🎉 Zeta Network prêt!");
     info!("📋 Bootstrap configurés: {:?}", bootstrap_addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>());
 
@@ -252,9 +1060,27 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let mut reconnect_interval = tokio::time::interval(Duration::from_secs(30));
     let bootstrap_clone = bootstrap_addrs.clone();
     let local_ip_clone = local_ip.clone();
+
+    // Rafraîchissement des scores Gossipsub exposés dans l'API
+    let mut score_interval = tokio::time::interval(Duration::from_secs(10));
+
+    // Relevés de disponibilité (mesh, stockage), dont l'âge révèle une boucle bloquée
+    let mut health_interval = tokio::time::interval(health::CHECK_INTERVAL);
+    let main_topic = channels::topic(&config.topic, None).hash();
+
+    // Rafraîchissement de la table de routage Kademlia (5 min)
+    let mut kad_interval = tokio::time::interval(Duration::from_secs(300));
     
     // Tracker les peers connectés
     let mut connected_peers: std::collections::HashSet<PeerId> = std::collections::HashSet::new();
+    // Peers avec qui l'historique a déjà été synchronisé
+    let mut synced_peers: std::collections::HashSet<PeerId> = std::collections::HashSet::new();
+    // Synchronisations en cours, par peer
+    let mut syncs: HashMap<PeerId, sync::Progress> = HashMap::new();
+    // Demandes de blobs en cours, par requête
+    let mut media_requests: HashMap<request_response::RequestId, String> = HashMap::new();
+    // Blobs lus hors de la boucle, à renvoyer aux peers qui les ont demandés
+    let (blob_tx, mut blob_rx) = mpsc::unbounded_channel::<(request_response::ResponseChannel<Vec<u8>>, Vec<u8>)>();
 
     use futures::StreamExt;
     
@@ -277,20 +1103,111 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 }
             }
 
+            _ = health_interval.tick() => {
+                let mesh_peers = swarm.behaviour().gossipsub.mesh_peers(&main_topic).count();
+                network_state.health.record(mesh_peers);
+                // Écriture synchronisée sur disque : hors de la boucle
+                if network_state.health.start_probe() {
+                    let store = network_state.store.clone();
+                    let health = network_state.health.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let storage = store.probe().map_err(|e| e.to_string());
+                        if let Err(ref e) = storage {
+                            warn!("⚠️ Stockage non inscriptible: {}", e);
+                        }
+                        health.finish_probe(storage);
+                    });
+                }
+            }
+
+            _ = kad_interval.tick() => {
+                // Échoue tant qu'aucun peer n'est connu ; la première connexion relance l'amorçage
+                if swarm.behaviour_mut().kademlia.bootstrap().is_ok() {
+                    info!("🗺️ Rafraîchissement Kademlia lancé");
+                }
+            }
+
+            _ = score_interval.tick() => {
+                let gossipsub = &swarm.behaviour().gossipsub;
+                let scores: HashMap<String, f64> = connected_peers
+                    .iter()
+                    .filter_map(|peer_id| gossipsub.peer_score(peer_id).map(|score| (peer_id.to_string(), score)))
+                    .collect();
+                network_state.update_peer_scores(&scores).await;
+
+                let mesh: BTreeMap<String, usize> = gossipsub
+                    .topics()
+                    .map(|topic| (topic.to_string(), gossipsub.mesh_peers(topic).count()))
+                    .collect();
+                network_state.metrics.set_swarm(connected_peers.len(), mesh);
+            }
+
+            Some(command) = channel_rx.recv() => {
+                let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                match command {
+                    channels::Command::Subscribe(channel) => {
+                        let channel_topic = channels::topic(&config.topic, Some(&channel));
+                        let _ = gossipsub.set_topic_params(channel_topic.clone(), scoring::topic_params());
+                        if let Ok(true) = gossipsub.subscribe(&channel_topic) {
+                            info!("📢 Abonné au canal {} ({})", channel, channel_topic);
+                        }
+                    }
+                    channels::Command::Unsubscribe(channel) => {
+                        let channel_topic = channels::topic(&config.topic, Some(&channel));
+                        if let Ok(true) = gossipsub.unsubscribe(&channel_topic) {
+                            info!("📴 Désabonné du canal {}", channel);
+                        }
+                    }
+                }
+            }
+
+            Some(media::Command::Fetch { hash, peer }) = media_rx.recv() => {
+                let peers: Vec<PeerId> = match peer {
+                    Some(peer) => vec![peer],
+                    None => {
+                        let known = network_state.peers.read().await;
+                        swarm
+                            .connected_peers()
+                            .filter(|p| {
+                                known
+                                    .get(&p.to_string())
+                                    .is_some_and(|info| info.protocols.iter().any(|proto| proto == media::MEDIA_PROTOCOL))
+                            })
+                            .take(media::FETCH_FANOUT)
+                            .copied()
+                            .collect()
+                    }
+                };
+                if peers.is_empty() {
+                    warn!("⚠️ Média {} introuvable: aucun peer à interroger", hash);
+                }
+                for peer in peers {
+                    let request_id = swarm.behaviour_mut().media.send_request(&peer, hash.as_bytes().to_vec());
+                    media_requests.insert(request_id, hash.clone());
+                }
+            }
+
+            Some((channel, blob)) = blob_rx.recv() => {
+                let _ = swarm.behaviour_mut().media.send_response(channel, blob);
+            }
+
             Some(msg) = ws_to_p2p_rx.recv() => {
-                if let Ok(json) = serde_json::to_vec(&msg) {
+                if let Ok(json) = protocol::encode(&msg) {
                     // Log le nombre de peers dans le mesh pour ce topic
-                    let mesh_peers = swarm.behaviour().gossipsub.mesh_peers(&topic.hash()).count();
-                    info!("📊 Mesh peers pour {}: {}", TOPIC, mesh_peers);
+                    let target = channels::message_topic(&config.topic, &msg);
+                    let mesh_peers = swarm.behaviour().gossipsub.mesh_peers(&target.hash()).count();
+                    info!("📊 Mesh peers pour {}: {}", target, mesh_peers);
                     
                     // Publier sur Gossipsub
-                    match swarm.behaviour_mut().gossipsub.publish(topic.clone(), json) {
+                    match swarm.behaviour_mut().gossipsub.publish(target, json) {
                         Ok(_) => {
                             if let NetworkMessage::Post(ref p) = msg {
+                                network_state.metrics.post_published(Source::WebSocket);
                                 info!("📤 Post propagé sur Gossipsub ({} mesh peers): {}", mesh_peers, p.content);
                             }
                         }
                         Err(e) => {
+                            network_state.metrics.publish_failed(&e);
                             warn!("⚠️ Gossipsub publish ({} mesh peers): {:?}", mesh_peers, e);
                         }
                     }
@@ -300,11 +1217,18 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
             Some(post) = post_rx.recv() => {
                 let msg = NetworkMessage::Post(post.clone());
-                if let Ok(json) = serde_json::to_vec(&msg) {
-                    let mesh_peers = swarm.behaviour().gossipsub.mesh_peers(&topic.hash()).count();
-                    match swarm.behaviour_mut().gossipsub.publish(topic.clone(), json) {
-                        Ok(_) => info!("📤 Post publié via REST ({} mesh peers): {}", mesh_peers, post.content),
-                        Err(e) => warn!("⚠️ Gossipsub publish ({} mesh peers): {:?}", mesh_peers, e),
+                if let Ok(json) = protocol::encode(&msg) {
+                    let target = channels::message_topic(&config.topic, &msg);
+                    let mesh_peers = swarm.behaviour().gossipsub.mesh_peers(&target.hash()).count();
+                    match swarm.behaviour_mut().gossipsub.publish(target, json) {
+                        Ok(_) => {
+                            network_state.metrics.post_published(Source::Api);
+                            info!("📤 Post publié via REST ({} mesh peers): {}", mesh_peers, post.content);
+                        }
+                        Err(e) => {
+                            network_state.metrics.publish_failed(&e);
+                            warn!("⚠️ Gossipsub publish ({} mesh peers): {:?}", mesh_peers, e);
+                        }
                     }
                     // Toujours ajouter localement même si Gossipsub échoue
                     network_state.add_post(post).await;
@@ -316,6 +1240,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     SwarmEvent::NewListenAddr { address, .. } => {
                         let full_addr = format!("{}/p2p/{}", address, local_peer_id);
                         info!("🎧 Écoute sur: {}", full_addr);
+                        network_state.listen_addrs.write().await.push(full_addr.clone());
                         if is_relay {
                             info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
                             info!("📋 BOOTSTRAP ADDR: {}", full_addr);
@@ -324,14 +1249,126 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     }
 
                     SwarmEvent::Behaviour(ZetaEvent::Gossipsub(gossipsub::Event::Message {
-                        message, propagation_source, ..
+                        message, message_id, propagation_source,
                     })) => {
-                        if let Ok(msg) = serde_json::from_slice::<NetworkMessage>(&message.data) {
-                            if let NetworkMessage::Post(post) = msg {
-                                info!("📨 Post reçu via Gossipsub de {}: {} - \"{}\"", 
+                        let origin = message.source.unwrap_or(propagation_source);
+                        let limits = &network_state.rate_limits;
+                        let moderation = &network_state.moderation;
+                        // Peers bloqués, puis limite par peer d'origine (pas celui qui relaie)
+                        let check_peers = || {
+                            moderation
+                                .check_peer(&origin.to_string())
+                                .and_then(|_| moderation.check_peer(&propagation_source.to_string()))
+                                .map_err(Rejection::Moderated)
+                                .and_then(|_| limits.check_peer(origin).map_err(Rejection::RateLimited))
+                        };
+                        let verdict = if message.data.len() > validation::MAX_MESSAGE_BYTES {
+                            Err(Rejection::Oversized)
+                        } else {
+                            match protocol::decode(&message.data) {
+                                Ok(NetworkMessage::Post(post)) => validation::validate_post(&post)
+                                    // Un post n'est accepté que sur le topic de son canal
+                                    .and_then(|_| match channels::topic(&config.topic, post.channel.as_deref()).hash() == message.topic {
+                                        true => Ok(()),
+                                        false => Err(Rejection::BadChannel),
+                                    })
+                                    .and_then(|_| moderation.check_post(&post).map_err(Rejection::Moderated))
+                                    // Copie tardive d'un post supprimé par son auteur : ni stockée ni relayée
+                                    .and_then(|_| match network_state.is_deleted(&post.id, &post.author) {
+                                        true => Err(Rejection::Deleted),
+                                        false => Ok(()),
+                                    })
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&post.author, Kind::Post).map_err(Rejection::RateLimited))
+                                    .inspect_err(|rejection| network_state.metrics.post_rejected(Source::Gossipsub, rejection))
+                                    .map(|_| Some(NetworkMessage::Post(post))),
+                                Ok(NetworkMessage::Reaction(reaction)) => validation::validate_reaction(&reaction)
+                                    .and_then(|_| moderation.check_reaction(&reaction).map_err(Rejection::Moderated))
+                                    .and_then(|_| network_state.known_post(&reaction.post_id))
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&reaction.author, Kind::Reaction).map_err(Rejection::RateLimited))
+                                    .map(|_| Some(NetworkMessage::Reaction(reaction))),
+                                Ok(NetworkMessage::Follow(follow)) => validation::validate_follow(&follow, true)
+                                    .and_then(|_| moderation.check_follow(&follow).map_err(Rejection::Moderated))
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&follow.author, Kind::Follow).map_err(Rejection::RateLimited))
+                                    .map(|_| Some(NetworkMessage::Follow(follow))),
+                                Ok(NetworkMessage::Unfollow(follow)) => validation::validate_follow(&follow, false)
+                                    .and_then(|_| moderation.check_follow(&follow).map_err(Rejection::Moderated))
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&follow.author, Kind::Follow).map_err(Rejection::RateLimited))
+                                    .map(|_| Some(NetworkMessage::Unfollow(follow))),
+                                Ok(NetworkMessage::DirectMessage(dm)) => validation::validate_direct_message(&dm)
+                                    .and_then(|_| moderation.check_direct_message(&dm).map_err(Rejection::Moderated))
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&dm.sender, Kind::DirectMessage).map_err(Rejection::RateLimited))
+                                    .map(|_| Some(NetworkMessage::DirectMessage(dm))),
+                                Ok(NetworkMessage::Profile(profile)) => validation::validate_profile(&profile)
+                                    .and_then(|_| moderation.check_profile(&profile).map_err(Rejection::Moderated))
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&profile.author, Kind::Profile).map_err(Rejection::RateLimited))
+                                    .map(|_| Some(NetworkMessage::Profile(profile))),
+                                // Appliquées avant le verdict : seul le stockage connaît l'auteur du post
+                                Ok(NetworkMessage::Edit(edit)) => match validation::validate_edit(&edit)
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&edit.author, Kind::Edit).map_err(Rejection::RateLimited))
+                                {
+                                    Ok(()) => network_state.apply_edit(edit).await.map(|_| None),
+                                    Err(e) => Err(e),
+                                },
+                                Ok(NetworkMessage::Delete(delete)) => match validation::validate_delete(&delete)
+                                    .and_then(|_| check_peers())
+                                    .and_then(|_| limits.check_author(&delete.author, Kind::Edit).map_err(Rejection::RateLimited))
+                                {
+                                    Ok(()) => network_state.apply_delete(delete).await.map(|_| None),
+                                    Err(e) => Err(e),
+                                },
+                                Ok(NetworkMessage::Heartbeat { .. }) => Ok(None),
+                                Err(e) => {
+                                    network_state.decode_stats.record(&e);
+                                    Err(Rejection::Undecodable(e))
+                                }
+                            }
+                        };
+
+                        // Le message n'est relayé au mesh qu'une fois accepté
+                        let acceptance = match &verdict {
+                            Ok(_) => MessageAcceptance::Accept,
+                            Err(rejection) => {
+                                warn!("🚫 Message Gossipsub de {} refusé: {}", propagation_source, rejection);
+                                rejection.acceptance()
+                            }
+                        };
+                        let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                            &message_id,
+                            &propagation_source,
+                            acceptance,
+                        );
+
+                        match verdict {
+                            Ok(Some(NetworkMessage::Post(post))) => {
+                                info!("📨 Post reçu via Gossipsub de {}: {} - \"{}\"",
                                       propagation_source, post.author_name, post.content);
+                                network_state.metrics.post_received(Source::Gossipsub);
                                 network_state.add_post(post).await;
                             }
+                            Ok(Some(NetworkMessage::Reaction(reaction))) => {
+                                info!("💬 Réaction {} reçue via Gossipsub pour {}", reaction.reaction, reaction.post_id);
+                                network_state.add_reaction(reaction).await;
+                            }
+                            Ok(Some(NetworkMessage::Profile(profile))) => {
+                                network_state.set_profile(profile).await;
+                            }
+                            Ok(Some(NetworkMessage::DirectMessage(dm))) => {
+                                network_state.add_direct_message(dm).await;
+                            }
+                            Ok(Some(NetworkMessage::Follow(follow))) => {
+                                network_state.set_follow(follow, true).await;
+                            }
+                            Ok(Some(NetworkMessage::Unfollow(follow))) => {
+                                network_state.set_follow(follow, false).await;
+                            }
+                            _ => {}
                         }
                     }
                     
@@ -346,12 +1383,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     SwarmEvent::Behaviour(ZetaEvent::Mdns(mdns::Event::Discovered(list))) => {
                         for (peer_id, addr) in list {
                             info!("🔍 Découvert via mDNS: {}", peer_id);
-                            swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                            swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                            if !swarm.is_connected(&peer_id) {
+                                let _ = swarm.dial(peer_id);
+                            }
                             network_state.add_peer(PeerInfo {
                                 peer_id: peer_id.to_string(),
                                 address: addr.to_string(),
-                                name: None,
-                                is_browser: false,
+                                ..Default::default()
                             }).await;
                         }
                     }
@@ -363,25 +1402,182 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         }
                     }
 
+                    SwarmEvent::Behaviour(ZetaEvent::Sync(request_response::Event::Message { peer, message })) => {
+                        match message {
+                            request_response::Message::Request { request, channel, .. } => {
+                                if network_state.moderation.check_peer(&peer.to_string()).is_err() {
+                                    info!("🛡️ Sync refusée au peer bloqué {}", peer);
+                                    continue;
+                                }
+                                let mut response = sync::answer(network_state.store.as_ref(), &request);
+                                response.posts.retain(|post| network_state.is_visible(post));
+                                info!(
+                                    "🔄 Sync: {} post(s) et {} suppression(s) envoyé(s) à {}",
+                                    response.posts.len(),
+                                    response.tombstones.len(),
+                                    peer
+                                );
+                                let _ = swarm.behaviour_mut().sync.send_response(channel, response);
+                            }
+                            request_response::Message::Response { response, .. } => {
+                                if network_state.moderation.check_peer(&peer.to_string()).is_err() {
+                                    syncs.remove(&peer);
+                                    continue;
+                                }
+                                let received = response.posts.len();
+                                // Suppressions d'abord : une copie du post venue d'ailleurs sera refusée
+                                for delete in response.tombstones {
+                                    let post_id = delete.post_id.clone();
+                                    let applied = match validation::validate_delete(&delete) {
+                                        Ok(()) => network_state.apply_delete(delete).await,
+                                        Err(e) => Err(e),
+                                    };
+                                    if let Err(e) = applied {
+                                        warn!("🚫 Sync: suppression de {} reçue de {} rejetée: {}", post_id, peer, e);
+                                    }
+                                }
+                                for post in response.posts {
+                                    match validation::validate_post(&post) {
+                                        Ok(()) => {
+                                            network_state.metrics.post_received(Source::Sync);
+                                            network_state.add_post(post).await;
+                                        }
+                                        Err(e) => {
+                                            network_state.metrics.post_rejected(Source::Sync, &e);
+                                            warn!("🚫 Sync: post {} de {} rejeté: {}", post.id, peer, e);
+                                        }
+                                    }
+                                }
+                                info!("🔄 Sync: {} post(s) reçu(s) de {}", received, peer);
+                                let Some(mut progress) = syncs.remove(&peer) else {
+                                    continue;
+                                };
+                                if let Some(cursor) = response.next_cursor {
+                                    match progress.next_request(cursor) {
+                                        Ok(request) => {
+                                            swarm.behaviour_mut().sync.send_request(&peer, request);
+                                            syncs.insert(peer, progress);
+                                        }
+                                        Err(e) => warn!("⚠️ Sync avec {} interrompue: {}", peer, e),
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    SwarmEvent::Behaviour(ZetaEvent::Sync(request_response::Event::OutboundFailure { peer, error, .. })) => {
+                        warn!("⚠️ Sync avec {} échouée: {}", peer, error);
+                        syncs.remove(&peer);
+                    }
+
+                    SwarmEvent::Behaviour(ZetaEvent::Media(request_response::Event::Message { peer, message })) => {
+                        match message {
+                            request_response::Message::Request { request, channel, .. } => {
+                                if network_state.moderation.check_peer(&peer.to_string()).is_err() {
+                                    continue;
+                                }
+                                // Lecture sur disque hors de la boucle ; réponse vide si le blob est absent
+                                let media = network_state.media.clone();
+                                let blob_tx = blob_tx.clone();
+                                tokio::task::spawn_blocking(move || {
+                                    let blob = std::str::from_utf8(&request)
+                                        .ok()
+                                        .and_then(|hash| media.get(hash).ok().flatten())
+                                        .unwrap_or_default();
+                                    let _ = blob_tx.send((channel, blob));
+                                });
+                            }
+                            request_response::Message::Response { request_id, response } => {
+                                let Some(hash) = media_requests.remove(&request_id) else {
+                                    continue;
+                                };
+                                if response.is_empty() || network_state.media.contains(&hash) {
+                                    continue;
+                                }
+                                let media = network_state.media.clone();
+                                tokio::task::spawn_blocking(move || match media.put_fetched(&hash, &response) {
+                                    Ok(info) => info!("🖼️ Média {} ({}, {} octets) reçu de {}", hash, info.mime, info.size, peer),
+                                    Err(e) => warn!("🚫 Média {} de {} refusé: {}", hash, peer, e),
+                                });
+                            }
+                        }
+                    }
+
+                    SwarmEvent::Behaviour(ZetaEvent::Media(request_response::Event::OutboundFailure { peer, request_id, error })) => {
+                        media_requests.remove(&request_id);
+                        warn!("⚠️ Demande de média à {} échouée: {}", peer, error);
+                    }
+
+                    SwarmEvent::Behaviour(ZetaEvent::Identify(event)) => {
+                        if let identify::Event::Received { peer_id, info } = *event {
+                            info!("🪪 Identify {}: {} ({} adresse(s))", peer_id, info.agent_version, info.listen_addrs.len());
+                            // Les adresses d'écoute routables alimentent Kademlia, qui les
+                            // transmet ensuite aux peers qui nous interrogent
+                            if info.protocols.iter().any(|p| p == KAD_PROTOCOL) {
+                                for addr in info.listen_addrs.iter().filter(|a| is_routable(a)) {
+                                    swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                                }
+                            }
+                            // L'adresse sous laquelle le peer nous voit est candidate comme adresse externe
+                            if is_relay && is_routable(&info.observed_addr) {
+                                swarm.add_external_address(info.observed_addr.clone(), AddressScore::Finite(1));
+                            }
+                            // Rattraper l'historique une fois par peer, s'il parle le protocole
+                            // (les navigateurs js-libp2p ne le supportent pas)
+                            if info.protocols.iter().any(|p| p == sync::SYNC_PROTOCOL) && synced_peers.insert(peer_id) {
+                                let request = sync::initial_request(network_state.store.as_ref());
+                                info!("🔄 Sync demandée à {} depuis {}", peer_id, request.since);
+                                syncs.insert(peer_id, sync::Progress::new(&request));
+                                swarm.behaviour_mut().sync.send_request(&peer_id, request);
+                            }
+                            network_state.update_peer_identity(&peer_id.to_string(), &info).await;
+                        }
+                    }
+
+                    SwarmEvent::Behaviour(ZetaEvent::Kademlia(KademliaEvent::RoutingUpdated { peer, is_new_peer: true, .. })) => {
+                        info!("🗺️ Kademlia: nouveau peer dans la table de routage: {}", peer);
+                        if !connected_peers.contains(&peer) && connected_peers.len() < MAX_DIALED_PEERS {
+                            // Kademlia fournit les adresses connues au moment du dial
+                            if let Err(e) = swarm.dial(peer) {
+                                warn!("⚠️ Échec connexion vers {}: {}", peer, e);
+                            }
+                        }
+                    }
+
                     SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                        if network_state.moderation.check_peer(&peer_id.to_string()).is_err() {
+                            info!("🛡️ Peer bloqué {} déconnecté", peer_id);
+                            swarm.behaviour_mut().gossipsub.blacklist_peer(&peer_id);
+                            let _ = swarm.disconnect_peer_id(peer_id);
+                            continue;
+                        }
                         info!("✅ Connecté à {}", peer_id);
                         info!("   Endpoint: {:?}", endpoint);
+                        let first_peer = connected_peers.is_empty();
                         connected_peers.insert(peer_id);
-                        swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                        // Seule l'adresse dialée est une adresse d'écoute stable du peer
+                        if let ConnectedPoint::Dialer { address, .. } = &endpoint {
+                            swarm.behaviour_mut().kademlia.add_address(&peer_id, address.clone());
+                            if first_peer {
+                                let _ = swarm.behaviour_mut().kademlia.bootstrap();
+                            }
+                        }
                         network_state.add_peer(PeerInfo {
                             peer_id: peer_id.to_string(),
-                            address: format!("{:?}", endpoint),
-                            name: None,
-                            is_browser: false,
+                            address: endpoint.get_remote_address().to_string(),
+                            ..Default::default()
                         }).await;
                         info!("📊 Total peers connectés: {}", connected_peers.len());
                     }
 
-                    SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+                    SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
                         info!("❌ Déconnecté de {}", peer_id);
                         if let Some(err) = cause {
                             info!("   Cause: {}", err);
                         }
+                        if num_established == 0 {
+                            synced_peers.remove(&peer_id);
+                        }
                         connected_peers.remove(&peer_id);
                         network_state.remove_peer(&peer_id.to_string()).await;
                         info!("📊 Total peers connectés: {}", connected_peers.len());
@@ -406,10 +1602,37 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 }
 
-fn load_or_create_keypair(path: &str) -> Result<libp2p::identity::Keypair, Box<dyn Error>> {
+/// Agent annoncé via Identify, qui transporte aussi le nom du nœud
+fn agent_version(local_name: &str) -> String {
+    format!("zeta-network/{} ({})", env!("CARGO_PKG_VERSION"), local_name)
+}
+
+/// Extrait le nom d'un agent produit par `agent_version`
+fn name_from_agent_version(agent: &str) -> Option<String> {
+    let rest = agent.strip_prefix("zeta-network/")?;
+    let (_, name) = rest.split_once(" (")?;
+    name.strip_suffix(')').map(str::to_string)
+}
+
+/// Les nœuds js-libp2p tournant dans un navigateur s'annoncent ainsi
+fn is_browser_agent(agent: &str) -> bool {
+    agent.contains("js-libp2p") || agent.contains("browser")
+}
+
+/// Adresse utile à transmettre à d'autres peers (ni loopback, ni non spécifiée)
+fn is_routable(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(Protocol::Ip4(ip)) => !ip.is_loopback() && !ip.is_unspecified(),
+        Some(Protocol::Ip6(ip)) => !ip.is_loopback() && !ip.is_unspecified(),
+        Some(Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_)) => true,
+        _ => false,
+    }
+}
+
+fn load_or_create_keypair(path: &Path) -> Result<libp2p::identity::Keypair, Box<dyn Error>> {
     use libp2p::identity::Keypair;
     
-    if Path::new(path).exists() {
+    if path.exists() {
         info!("🔐 Chargement de la clé existante...");
         let bytes = fs::read(path)?;
         Ok(Keypair::from_protobuf_encoding(&bytes)?)
@@ -417,23 +1640,22 @@ fn load_or_create_keypair(path: &str) -> Result<libp2p::identity::Keypair, Box<d
         info!("🔑 Génération d'une nouvelle clé...");
         let key = Keypair::generate_ed25519();
         fs::write(path, key.to_protobuf_encoding()?)?;
-        info!("💾 Clé sauvegardée dans {}", path);
+        info!("💾 Clé sauvegardée dans {}", path.display());
         Ok(key)
     }
 }
 
-fn load_bootstrap_addrs() -> Vec<Multiaddr> {
-    let path = "bootstrap.txt";
+fn load_bootstrap_addrs(path: &Path) -> Vec<Multiaddr> {
     let mut addrs = Vec::new();
     
-    if !Path::new(path).exists() {
+    if !path.exists() {
         let example = "# Bootstrap peers Zeta Network\n# Format: /ip4/IP/tcp/PORT (Peer ID not required)\n# Example: /ip4/65.75.201.11/tcp/4001\n";
         let _ = fs::write(path, example);
         return addrs;
     }
 
     if let Ok(file) = fs::File::open(path) {
-        for line in BufReader::new(file).lines().flatten() {
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
             let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
@@ -442,7 +1664,7 @@ fn load_bootstrap_addrs() -> Vec<Multiaddr> {
                 info!("📋 Bootstrap configuré: {}", addr);
                 addrs.push(addr);
             } else {
-                warn!("⚠️ Adresse invalide dans bootstrap.txt: {}", line);
+                warn!("⚠️ Adresse invalide dans {}: {}", path.display(), line);
             }
         }
     }
@@ -0,0 +1,330 @@
+//! Pièces jointes adressées par leur contenu
+//!
+//! Un blob est identifié par le SHA-256 de ses octets (hex) et stocké dans
+//! `<data_dir>/media/<hash>`. Les navigateurs l'envoient par `POST /api/media`,
+//! les posts n'en portent que le hash et des métadonnées signées. Un relais
+//! qui ne possède pas un blob le demande à ses peers par le protocole
+//! request-response `/zeta/media/1.0.0`, et vérifie le hash de la réponse
+//! ainsi que le type et la taille déclarés par la pièce jointe. Le nombre de
+//! demandes en cours est plafonné, avec une part réservée aux lectures
+//! `GET /media/{hash}` face au préchargement des posts reçus.
+//!
+//! Le type servi est toujours celui détecté sur les octets (MIME sniffing),
+//! jamais celui déclaré par l'envoyeur.
+use crate::codec::{BytesCodec, Protocol};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+
+pub const MEDIA_PROTOCOL: &str = "/zeta/media/1.0.0";
+pub const MAX_MEDIA_BYTES: usize = 5 * 1024 * 1024;
+pub const MAX_ATTACHMENTS: usize = 4;
+pub const MAX_ALT_CHARS: usize = 1000;
+pub const MAX_NAME_CHARS: usize = 200;
+/// Attente maximale d'un blob demandé aux peers par `GET /media/{hash}`
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Peers interrogés en parallèle quand la source d'un blob est inconnue
+pub const FETCH_FANOUT: usize = 3;
+/// Un blob déjà demandé n'est pas redemandé avant ce délai
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+/// Blobs demandés aux peers depuis moins de `RETRY_AFTER`, toutes origines confondues
+pub const MAX_PENDING_FETCHES: usize = 32;
+/// Part de `MAX_PENDING_FETCHES` ouverte au préchargement des pièces jointes
+/// des posts reçus ; le reste est gardé pour `GET /media/{hash}`
+pub const MAX_PREFETCHES: usize = 16;
+/// Taille d'une requête du protocole : un hash hex
+pub const HASH_CHARS: usize = 64;
+
+/// Types reconnus par `sniff`, les seuls acceptés dans une pièce jointe
+pub const KNOWN_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+    "audio/mpeg",
+    "audio/ogg",
+    "application/pdf",
+    "application/zip",
+    "text/plain",
+    "application/octet-stream",
+];
+
+/// Pièce jointe d'un post ; le blob lui-même circule hors de Gossipsub
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// SHA-256 du blob (hex)
+    pub hash: String,
+    pub mime: String,
+    pub size: u64,
+    /// Nom du fichier d'origine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Texte alternatif
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+}
+
+/// Blob stocké, tel que renvoyé par `POST /api/media`
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaInfo {
+    pub hash: String,
+    pub mime: &'static str,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+pub enum MediaError {
+    Empty,
+    TooLarge,
+    HashMismatch,
+    /// Type ou taille déclarés par la pièce jointe différents du blob
+    Mismatch,
+    Missing,
+    /// Trop de blobs déjà demandés aux peers
+    Busy,
+    Io(io::Error),
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaError::Empty => write!(f, "fichier vide"),
+            MediaError::TooLarge => write!(f, "fichier trop volumineux (max {} octets)", MAX_MEDIA_BYTES),
+            MediaError::HashMismatch => write!(f, "contenu différent du hash demandé"),
+            MediaError::Mismatch => write!(f, "type ou taille différents du contenu"),
+            MediaError::Missing => write!(f, "absente de ce relais"),
+            MediaError::Busy => write!(f, "trop de médias en cours de téléchargement, réessayez plus tard"),
+            MediaError::Io(e) => write!(f, "stockage des médias inaccessible: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MediaError {}
+
+impl From<io::Error> for MediaError {
+    fn from(e: io::Error) -> Self {
+        MediaError::Io(e)
+    }
+}
+
+/// Demandes à exécuter par la boucle libp2p
+#[derive(Debug)]
+pub enum Command {
+    /// Demander un blob à `peer`, ou à quelques peers connectés
+    Fetch { hash: String, peer: Option<PeerId> },
+}
+
+pub type Behaviour = request_response::Behaviour<BytesCodec>;
+pub type Event = request_response::Event<Vec<u8>, Vec<u8>>;
+
+pub fn behaviour() -> Behaviour {
+    let mut config = request_response::Config::default();
+    config.set_request_timeout(Duration::from_secs(30));
+    request_response::Behaviour::new(
+        BytesCodec::new(HASH_CHARS, MAX_MEDIA_BYTES),
+        [(Protocol(MEDIA_PROTOCOL), ProtocolSupport::Full)],
+        config,
+    )
+}
+
+pub fn hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == HASH_CHARS && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Type MIME d'après les premiers octets ; le texte UTF-8 non reconnu est
+/// servi en `text/plain`, tout le reste en `application/octet-stream`
+pub fn sniff(bytes: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return mime;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return "text/plain";
+    }
+    "application/octet-stream"
+}
+
+/// Taille des blobs d'un répertoire, sans les écritures `.tmp` en cours
+fn blobs_size(dir: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_valid_hash(&entry.file_name().to_string_lossy()) {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Blob demandé aux peers
+struct Pending {
+    since: Instant,
+    /// Type et taille déclarés par la pièce jointe qui a motivé la demande
+    expected: Option<(String, u64)>,
+}
+
+pub struct MediaStore {
+    dir: PathBuf,
+    /// Taille cumulée des blobs, mesurée à l'ouverture puis tenue à jour par `put`
+    stored_bytes: AtomicU64,
+    pending: Mutex<HashMap<String, Pending>>,
+    /// Hash de chaque blob reçu d'un peer, pour réveiller `GET /media/{hash}`
+    fetched: broadcast::Sender<String>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl MediaStore {
+    pub fn open(dir: impl AsRef<Path>, commands: mpsc::UnboundedSender<Command>) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        let (fetched, _) = broadcast::channel(64);
+        Ok(Self {
+            stored_bytes: AtomicU64::new(blobs_size(dir.as_ref())?),
+            dir: dir.as_ref().to_path_buf(),
+            pending: Mutex::new(HashMap::new()),
+            fetched,
+            commands,
+        })
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.dir.join(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        is_valid_hash(hash) && self.path(hash).is_file()
+    }
+
+    pub fn get(&self, hash: &str) -> io::Result<Option<Vec<u8>>> {
+        if !is_valid_hash(hash) {
+            return Ok(None);
+        }
+        match fs::read(self.path(hash)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stocke un blob envoyé par un client
+    pub fn put(&self, bytes: &[u8]) -> Result<MediaInfo, MediaError> {
+        if bytes.is_empty() {
+            return Err(MediaError::Empty);
+        }
+        if bytes.len() > MAX_MEDIA_BYTES {
+            return Err(MediaError::TooLarge);
+        }
+        let hash = hash(bytes);
+        let path = self.path(&hash);
+        if !path.is_file() {
+            // Écriture puis renommage : un blob n'est jamais servi à moitié écrit
+            let tmp = self.dir.join(format!("{}.tmp", hash));
+            fs::write(&tmp, bytes)?;
+            fs::rename(&tmp, &path)?;
+            self.stored_bytes.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        }
+        Ok(MediaInfo { hash, mime: sniff(bytes), size: bytes.len() as u64 })
+    }
+
+    /// Vérifie qu'une pièce jointe décrit bien un blob présent sur ce relais
+    pub fn check(&self, attachment: &Attachment) -> Result<(), MediaError> {
+        let blob = self.get(&attachment.hash)?.ok_or(MediaError::Missing)?;
+        if blob.len() as u64 != attachment.size || sniff(&blob) != attachment.mime {
+            return Err(MediaError::Mismatch);
+        }
+        Ok(())
+    }
+
+    /// Stocke un blob reçu d'un peer, s'il correspond au hash demandé et à la
+    /// pièce jointe qui l'a fait demander
+    pub fn put_fetched(&self, hash_hex: &str, bytes: &[u8]) -> Result<MediaInfo, MediaError> {
+        if hash(bytes) != hash_hex {
+            return Err(MediaError::HashMismatch);
+        }
+        // L'entrée reste en attente en cas d'écart : le même contenu reviendrait
+        let expected = self.pending.lock().unwrap().get(hash_hex).and_then(|p| p.expected.clone());
+        if expected.is_some_and(|(mime, size)| size != bytes.len() as u64 || mime != sniff(bytes)) {
+            return Err(MediaError::Mismatch);
+        }
+        let info = self.put(bytes)?;
+        self.pending.lock().unwrap().remove(hash_hex);
+        let _ = self.fetched.send(info.hash.clone());
+        Ok(info)
+    }
+
+    /// Demande un blob absent aux peers, sauf s'il l'a été récemment ;
+    /// renvoie `false` si le blob est déjà là
+    pub fn request(&self, hash: &str, peer: Option<PeerId>) -> Result<bool, MediaError> {
+        self.enqueue(hash, None, peer, MAX_PENDING_FETCHES)
+    }
+
+    /// Précharge le blob d'une pièce jointe reçue, dans la limite de `MAX_PREFETCHES`
+    pub fn prefetch(&self, attachment: &Attachment) -> Result<bool, MediaError> {
+        let expected = Some((attachment.mime.clone(), attachment.size));
+        self.enqueue(&attachment.hash, expected, None, MAX_PREFETCHES)
+    }
+
+    fn enqueue(
+        &self,
+        hash: &str,
+        expected: Option<(String, u64)>,
+        peer: Option<PeerId>,
+        cap: usize,
+    ) -> Result<bool, MediaError> {
+        if !is_valid_hash(hash) || self.contains(hash) {
+            return Ok(false);
+        }
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.since.elapsed() < RETRY_AFTER);
+        if pending.contains_key(hash) {
+            return Ok(true);
+        }
+        if pending.len() >= cap {
+            return Err(MediaError::Busy);
+        }
+        pending.insert(hash.to_string(), Pending { since: Instant::now(), expected });
+        let _ = self.commands.send(Command::Fetch { hash: hash.to_string(), peer });
+        Ok(true)
+    }
+
+    /// Taille cumulée des blobs stockés, en octets
+    pub fn stored_bytes(&self) -> u64 {
+        self.stored_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.fetched.subscribe()
+    }
+}
@@ -0,0 +1,187 @@
+//! Métriques Prometheus du relais
+//!
+//! Les compteurs sont incrémentés là où les événements se produisent
+//! (Gossipsub, synchronisation, API REST, WebSocket). Les jauges du swarm
+//! (peers connectés, mesh par topic) ne sont lisibles que depuis la boucle
+//! principale, qui les recopie ici à chaque tick. `GET /metrics` rend le tout
+//! au format texte de Prometheus.
+use crate::validation::Rejection;
+use libp2p::gossipsub::PublishError;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Origine d'un post
+#[derive(Debug, Clone, Copy)]
+pub enum Source {
+    Gossipsub,
+    Sync,
+    Api,
+    WebSocket,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Gossipsub => "gossipsub",
+            Source::Sync => "sync",
+            Source::Api => "api",
+            Source::WebSocket => "websocket",
+        }
+    }
+}
+
+/// Compteurs d'une même métrique, par valeurs de labels
+struct CounterVec {
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<&'static str>, u64>>,
+}
+
+impl CounterVec {
+    const fn new(labels: &'static [&'static str]) -> Self {
+        Self { labels, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    fn inc_by(&self, values: &[&'static str], n: u64) {
+        *self.values.lock().unwrap().entry(values.to_vec()).or_insert(0) += n;
+    }
+
+    fn inc(&self, values: &[&'static str]) {
+        self.inc_by(values, 1);
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        header(out, name, help, "counter");
+        for (values, count) in self.values.lock().unwrap().iter() {
+            let labels: Vec<(&str, &str)> = self.labels.iter().copied().zip(values.iter().copied()).collect();
+            sample(out, name, &labels, *count);
+        }
+    }
+}
+
+pub struct Metrics {
+    posts_received: CounterVec,
+    posts_published: CounterVec,
+    posts_rejected: CounterVec,
+    publish_errors: CounterVec,
+    broadcast_lagged: CounterVec,
+    ws_clients: AtomicU64,
+    connected_peers: AtomicU64,
+    /// Topic -> peers du mesh, recopié par la boucle principale
+    mesh_peers: RwLock<BTreeMap<String, usize>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            posts_received: CounterVec::new(&["source"]),
+            posts_published: CounterVec::new(&["source"]),
+            posts_rejected: CounterVec::new(&["source", "reason"]),
+            publish_errors: CounterVec::new(&["error"]),
+            broadcast_lagged: CounterVec::new(&["channel"]),
+            ws_clients: AtomicU64::new(0),
+            connected_peers: AtomicU64::new(0),
+            mesh_peers: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Post accepté
+    pub fn post_received(&self, source: Source) {
+        self.posts_received.inc(&[source.as_str()]);
+    }
+
+    /// Post diffusé sur Gossipsub
+    pub fn post_published(&self, source: Source) {
+        self.posts_published.inc(&[source.as_str()]);
+    }
+
+    pub fn post_rejected(&self, source: Source, rejection: &Rejection) {
+        self.posts_rejected.inc(&[source.as_str(), rejection.reason()]);
+    }
+
+    pub fn publish_failed(&self, error: &PublishError) {
+        let label = match error {
+            PublishError::Duplicate => "duplicate",
+            PublishError::SigningError(_) => "signing",
+            PublishError::InsufficientPeers => "insufficient_peers",
+            PublishError::MessageTooLarge => "message_too_large",
+            PublishError::TransformFailed(_) => "transform_failed",
+        };
+        self.publish_errors.inc(&[label]);
+    }
+
+    /// Messages d'un broadcast perdus par un client WebSocket trop lent
+    pub fn broadcast_lagged(&self, channel: &'static str, skipped: u64) {
+        self.broadcast_lagged.inc_by(&[channel], skipped);
+    }
+
+    pub fn ws_connected(&self) {
+        self.ws_clients.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ws_disconnected(&self) {
+        self.ws_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// État du swarm, fourni par la boucle principale
+    pub fn set_swarm(&self, connected_peers: usize, mesh_peers: BTreeMap<String, usize>) {
+        self.connected_peers.store(connected_peers as u64, Ordering::Relaxed);
+        *self.mesh_peers.write().unwrap() = mesh_peers;
+    }
+
+    /// Texte servi par `/metrics` ; les tailles de stockage sont mesurées par l'appelant
+    pub fn render(&self, stored_posts: usize, storage_bytes: u64, media_bytes: u64) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "zeta_connected_peers", "Peers libp2p connectés", "gauge");
+        sample(&mut out, "zeta_connected_peers", &[], self.connected_peers.load(Ordering::Relaxed));
+        header(&mut out, "zeta_mesh_peers", "Peers du mesh Gossipsub, par topic", "gauge");
+        for (topic, peers) in self.mesh_peers.read().unwrap().iter() {
+            sample(&mut out, "zeta_mesh_peers", &[("topic", topic)], *peers as u64);
+        }
+        header(&mut out, "zeta_ws_clients", "Clients WebSocket connectés", "gauge");
+        sample(&mut out, "zeta_ws_clients", &[], self.ws_clients.load(Ordering::Relaxed));
+
+        self.posts_received.render(&mut out, "zeta_posts_received_total", "Posts acceptés, par origine");
+        self.posts_published.render(&mut out, "zeta_posts_published_total", "Posts publiés sur Gossipsub, par origine");
+        self.posts_rejected.render(&mut out, "zeta_posts_rejected_total", "Posts refusés, par origine et par motif");
+        self.publish_errors.render(&mut out, "zeta_publish_errors_total", "Échecs de publication Gossipsub, par erreur");
+        self.broadcast_lagged.render(
+            &mut out,
+            "zeta_broadcast_lagged_total",
+            "Messages perdus par des clients WebSocket trop lents, par canal",
+        );
+
+        header(&mut out, "zeta_stored_posts", "Posts stockés", "gauge");
+        sample(&mut out, "zeta_stored_posts", &[], stored_posts as u64);
+        header(&mut out, "zeta_storage_bytes", "Taille de la base sur disque", "gauge");
+        sample(&mut out, "zeta_storage_bytes", &[], storage_bytes);
+        header(&mut out, "zeta_media_bytes", "Taille des pièces jointes sur disque", "gauge");
+        sample(&mut out, "zeta_media_bytes", &[], media_bytes);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
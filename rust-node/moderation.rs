@@ -0,0 +1,225 @@
+//! Modération locale du relais
+//!
+//! Les règles (auteurs et peers bloqués, mots-clés, expressions régulières,
+//! posts retirés) sont propres à chaque relais et enregistrées dans
+//! `moderation.json`. Un post refusé n'est ni stocké, ni servi, ni relayé.
+use crate::{DirectMessage, Edit, Follow, Post, Profile, Reaction};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Règles telles qu'enregistrées sur disque et exposées par `/api/moderation`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    /// Clés publiques d'auteurs (hex)
+    pub blocked_authors: BTreeSet<String>,
+    /// PeerId libp2p dont les messages et la synchronisation sont refusés
+    pub blocked_peers: BTreeSet<String>,
+    /// Mots-clés recherchés sans tenir compte de la casse
+    pub keywords: Vec<String>,
+    /// Expressions régulières (syntaxe du crate `regex`)
+    pub patterns: Vec<String>,
+    /// Posts retirés manuellement, qui ne seront plus acceptés
+    pub removed_posts: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub enum Verdict {
+    BlockedAuthor,
+    BlockedPeer,
+    Keyword(String),
+    Pattern(String),
+    Removed,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::BlockedAuthor => write!(f, "auteur bloqué"),
+            Verdict::BlockedPeer => write!(f, "peer bloqué"),
+            Verdict::Keyword(keyword) => write!(f, "mot-clé interdit \"{}\"", keyword),
+            Verdict::Pattern(pattern) => write!(f, "règle /{}/", pattern),
+            Verdict::Removed => write!(f, "post retiré par le relais"),
+        }
+    }
+}
+
+struct Compiled {
+    rules: Rules,
+    /// Mots-clés en minuscules
+    keywords: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Compiled {
+    fn new(rules: Rules) -> Result<Self, String> {
+        let patterns = rules
+            .patterns
+            .iter()
+            .map(|p| {
+                RegexBuilder::new(p)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| format!("expression invalide /{}/: {}", p, e))
+            })
+            .collect::<Result<_, _>>()?;
+        let keywords = rules
+            .keywords
+            .iter()
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty())
+            .collect();
+        Ok(Self { rules, keywords, patterns })
+    }
+
+    fn check_text(&self, text: &str) -> Result<(), Verdict> {
+        let lower = text.to_lowercase();
+        if let Some(keyword) = self.keywords.iter().find(|k| lower.contains(k.as_str())) {
+            return Err(Verdict::Keyword(keyword.clone()));
+        }
+        if let Some(pattern) = self.patterns.iter().find(|p| p.is_match(text)) {
+            return Err(Verdict::Pattern(pattern.as_str().to_string()));
+        }
+        Ok(())
+    }
+}
+
+pub struct Moderation {
+    path: PathBuf,
+    state: RwLock<Compiled>,
+}
+
+impl Moderation {
+    /// Charge les règles depuis `path` ; un fichier absent donne des règles vides
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let rules = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Rules::default(),
+            Err(e) => return Err(format!("lecture de {} impossible: {}", path.display(), e)),
+        };
+        Ok(Self {
+            path,
+            state: RwLock::new(Compiled::new(rules)?),
+        })
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.state.read().unwrap().rules.clone()
+    }
+
+    /// Vérifie un post (auteur, id, nom affiché et contenu)
+    pub fn check_post(&self, post: &Post) -> Result<(), Verdict> {
+        let state = self.state.read().unwrap();
+        if state.rules.removed_posts.contains(&post.id) {
+            return Err(Verdict::Removed);
+        }
+        if state.rules.blocked_authors.contains(&post.author) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        state.check_text(&post.content)?;
+        state.check_text(&post.author_name)
+    }
+
+    /// Vérifie une modification : post retiré, auteur bloqué ou nouveau contenu interdit
+    pub fn check_edit(&self, edit: &Edit) -> Result<(), Verdict> {
+        let state = self.state.read().unwrap();
+        if state.rules.removed_posts.contains(&edit.post_id) {
+            return Err(Verdict::Removed);
+        }
+        if state.rules.blocked_authors.contains(&edit.author) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        state.check_text(&edit.content)
+    }
+
+    /// Vérifie un profil : auteur bloqué, nom affiché ou bio interdits
+    pub fn check_profile(&self, profile: &Profile) -> Result<(), Verdict> {
+        let state = self.state.read().unwrap();
+        if state.rules.blocked_authors.contains(&profile.author) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        state.check_text(&profile.display_name)?;
+        state.check_text(&profile.bio)
+    }
+
+    pub fn check_follow(&self, follow: &Follow) -> Result<(), Verdict> {
+        if self.state.read().unwrap().rules.blocked_authors.contains(&follow.author) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        Ok(())
+    }
+
+    /// Le contenu est chiffré : seul l'expéditeur peut être bloqué
+    pub fn check_direct_message(&self, dm: &DirectMessage) -> Result<(), Verdict> {
+        if self.state.read().unwrap().rules.blocked_authors.contains(&dm.sender) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        Ok(())
+    }
+
+    /// Vérifie une réaction : auteur bloqué ou post retiré
+    pub fn check_reaction(&self, reaction: &Reaction) -> Result<(), Verdict> {
+        let state = self.state.read().unwrap();
+        if state.rules.removed_posts.contains(&reaction.post_id) {
+            return Err(Verdict::Removed);
+        }
+        if state.rules.blocked_authors.contains(&reaction.author) {
+            return Err(Verdict::BlockedAuthor);
+        }
+        Ok(())
+    }
+
+    pub fn check_peer(&self, peer_id: &str) -> Result<(), Verdict> {
+        if self.state.read().unwrap().rules.blocked_peers.contains(peer_id) {
+            return Err(Verdict::BlockedPeer);
+        }
+        Ok(())
+    }
+
+    pub fn is_allowed(&self, post: &Post) -> bool {
+        self.check_post(post).is_ok()
+    }
+
+    /// Remplace les règles ; `removed_posts` n'est modifié que par `remove_post`
+    pub fn replace(&self, rules: Rules) -> Result<(), String> {
+        self.update(|current| {
+            *current = Rules {
+                removed_posts: std::mem::take(&mut current.removed_posts),
+                ..rules
+            }
+        })
+    }
+
+    /// Ajoute un post à la liste des posts retirés
+    pub fn remove_post(&self, id: &str) -> Result<(), String> {
+        self.update(|rules| {
+            rules.removed_posts.insert(id.to_string());
+        })
+    }
+
+    /// Applique `change` à une copie des règles, la valide, l'enregistre puis l'active
+    fn update(&self, change: impl FnOnce(&mut Rules)) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        let mut rules = state.rules.clone();
+        change(&mut rules);
+        let compiled = Compiled::new(rules)?;
+        save(&self.path, &compiled.rules)?;
+        *state = compiled;
+        Ok(())
+    }
+}
+
+/// Écriture atomique : fichier temporaire puis renommage
+fn save(path: &Path, rules: &Rules) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(rules).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("écriture de {} impossible: {}", path.display(), e))
+}
@@ -0,0 +1,157 @@
+//! Enveloppe versionnée des messages Gossipsub
+//!
+//! Format : `{"version": 1, "kind": "post", "payload": {...}}`.
+//!
+//! Règles de compatibilité :
+//! - ajouter un champ optionnel ou un nouveau `kind` ne change pas `version` ;
+//!   un relais qui ne connaît pas un `kind` l'ignore (compté dans `unknown_kind`) ;
+//! - un changement incompatible incrémente `version` ; un message d'une version
+//!   plus récente que `PROTOCOL_VERSION` est ignoré, sans pénaliser le peer qui
+//!   le relaie (compté dans `unsupported_version`) ;
+//! - les messages sans enveloppe (relais antérieurs) sont encore acceptés.
+use crate::{Delete, DirectMessage, Edit, Follow, NetworkMessage, Post, Profile, Reaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kind: String,
+    #[serde(default)]
+    payload: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeartbeatPayload {
+    peer_id: String,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Ni une enveloppe, ni un message sans enveloppe valide
+    Malformed(serde_json::Error),
+    /// Version plus récente que celle supportée
+    UnsupportedVersion(u32),
+    /// `kind` inconnu de ce relais
+    UnknownKind(String),
+    /// `kind` connu mais payload invalide
+    BadPayload { kind: String, error: serde_json::Error },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Malformed(e) => write!(f, "message illisible: {}", e),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(f, "version {} non supportée (max {})", v, PROTOCOL_VERSION)
+            }
+            DecodeError::UnknownKind(kind) => write!(f, "kind inconnu: {}", kind),
+            DecodeError::BadPayload { kind, error } => write!(f, "payload {} invalide: {}", kind, error),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+pub fn encode(msg: &NetworkMessage) -> Result<Vec<u8>, serde_json::Error> {
+    let (kind, payload) = match msg {
+        NetworkMessage::Post(post) => ("post", serde_json::to_value(post)?),
+        NetworkMessage::Heartbeat { peer_id } => (
+            "heartbeat",
+            serde_json::to_value(HeartbeatPayload { peer_id: peer_id.clone() })?,
+        ),
+        NetworkMessage::Reaction(reaction) => ("reaction", serde_json::to_value(reaction)?),
+        NetworkMessage::Edit(edit) => ("edit", serde_json::to_value(edit)?),
+        NetworkMessage::Delete(delete) => ("delete", serde_json::to_value(delete)?),
+        NetworkMessage::Profile(profile) => ("profile", serde_json::to_value(profile)?),
+        NetworkMessage::Follow(follow) => ("follow", serde_json::to_value(follow)?),
+        NetworkMessage::Unfollow(follow) => ("unfollow", serde_json::to_value(follow)?),
+        NetworkMessage::DirectMessage(dm) => ("dm", serde_json::to_value(dm)?),
+    };
+    serde_json::to_vec(&Envelope {
+        version: PROTOCOL_VERSION,
+        kind: kind.to_string(),
+        payload,
+    })
+}
+
+pub fn decode(bytes: &[u8]) -> Result<NetworkMessage, DecodeError> {
+    let envelope = match serde_json::from_slice::<Envelope>(bytes) {
+        Ok(envelope) => envelope,
+        // Message sans enveloppe, envoyé par un relais antérieur
+        Err(e) => return serde_json::from_slice::<NetworkMessage>(bytes).map_err(|_| DecodeError::Malformed(e)),
+    };
+
+    if envelope.version > PROTOCOL_VERSION {
+        return Err(DecodeError::UnsupportedVersion(envelope.version));
+    }
+
+    let bad_payload = |error| DecodeError::BadPayload {
+        kind: envelope.kind.clone(),
+        error,
+    };
+    match envelope.kind.as_str() {
+        "post" => serde_json::from_value::<Post>(envelope.payload.clone())
+            .map(NetworkMessage::Post)
+            .map_err(bad_payload),
+        "heartbeat" => serde_json::from_value::<HeartbeatPayload>(envelope.payload.clone())
+            .map(|h| NetworkMessage::Heartbeat { peer_id: h.peer_id })
+            .map_err(bad_payload),
+        "reaction" => serde_json::from_value::<Reaction>(envelope.payload.clone())
+            .map(NetworkMessage::Reaction)
+            .map_err(bad_payload),
+        "edit" => serde_json::from_value::<Edit>(envelope.payload.clone())
+            .map(NetworkMessage::Edit)
+            .map_err(bad_payload),
+        "delete" => serde_json::from_value::<Delete>(envelope.payload.clone())
+            .map(NetworkMessage::Delete)
+            .map_err(bad_payload),
+        "profile" => serde_json::from_value::<Profile>(envelope.payload.clone())
+            .map(NetworkMessage::Profile)
+            .map_err(bad_payload),
+        "follow" => serde_json::from_value::<Follow>(envelope.payload.clone())
+            .map(NetworkMessage::Follow)
+            .map_err(bad_payload),
+        "unfollow" => serde_json::from_value::<Follow>(envelope.payload.clone())
+            .map(NetworkMessage::Unfollow)
+            .map_err(bad_payload),
+        "dm" => serde_json::from_value::<DirectMessage>(envelope.payload.clone())
+            .map(NetworkMessage::DirectMessage)
+            .map_err(bad_payload),
+        _ => Err(DecodeError::UnknownKind(envelope.kind)),
+    }
+}
+
+/// Compteurs des messages Gossipsub qui n'ont pas pu être décodés
+#[derive(Debug, Default)]
+pub struct DecodeStats {
+    pub malformed: AtomicU64,
+    pub unsupported_version: AtomicU64,
+    pub unknown_kind: AtomicU64,
+    pub bad_payload: AtomicU64,
+}
+
+impl DecodeStats {
+    pub fn record(&self, error: &DecodeError) {
+        let counter = match error {
+            DecodeError::Malformed(_) => &self.malformed,
+            DecodeError::UnsupportedVersion(_) => &self.unsupported_version,
+            DecodeError::UnknownKind(_) => &self.unknown_kind,
+            DecodeError::BadPayload { .. } => &self.bad_payload,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Value {
+        serde_json::json!({
+            "malformed": self.malformed.load(Ordering::Relaxed),
+            "unsupported_version": self.unsupported_version.load(Ordering::Relaxed),
+            "unknown_kind": self.unknown_kind.load(Ordering::Relaxed),
+            "bad_payload": self.bad_payload.load(Ordering::Relaxed),
+        })
+    }
+}
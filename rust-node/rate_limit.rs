@@ -0,0 +1,307 @@
+//! Limitation de débit des messages signés (token bucket)
+//!
+//! Quatre portées : session WebSocket, IP distante, clé d'auteur et peer
+//! Gossipsub d'origine. Aux trois premières, chaque type de message a ses
+//! propres buckets : aimer quelques posts ne retarde pas le post suivant.
+//! Une cinquième portée, par IP, borne les lectures de médias
+//! absents, qui coûtent des requêtes aux peers. Chaque refus incrémente un
+//! compteur visible dans `GET /api/network`.
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use libp2p::PeerId;
+
+/// Nombre maximal de clés suivies : au-delà, les buckets pleins sont oubliés,
+/// puis les moins récemment utilisés
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Paramètres d'un bucket : `burst` messages d'affilée, puis `per_minute`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// Section `[rate_limits]` du fichier de configuration ; `session`, `ip` et
+/// `author` portent sur les posts, les autres types de message ont chacun
+/// une limite commune à ces trois portées
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    pub session: Limit,
+    pub ip: Limit,
+    pub author: Limit,
+    pub peer: Limit,
+    pub reaction: Limit,
+    /// Abonnements et désabonnements
+    pub follow: Limit,
+    pub profile: Limit,
+    pub dm: Limit,
+    /// Modifications et suppressions
+    pub edit: Limit,
+    /// Canaux rejoints par les sessions WebSocket
+    pub channel: Limit,
+    /// `POST /api/media`, par IP
+    pub media: Limit,
+    /// `GET /media/{hash}` d'un blob à demander aux peers, par IP
+    pub fetch: Limit,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            session: Limit { burst: 5, per_minute: 10 },
+            ip: Limit { burst: 10, per_minute: 30 },
+            author: Limit { burst: 5, per_minute: 10 },
+            peer: Limit { burst: 50, per_minute: 300 },
+            reaction: Limit { burst: 30, per_minute: 60 },
+            follow: Limit { burst: 20, per_minute: 30 },
+            profile: Limit { burst: 3, per_minute: 5 },
+            dm: Limit { burst: 10, per_minute: 30 },
+            edit: Limit { burst: 10, per_minute: 20 },
+            channel: Limit { burst: 8, per_minute: 10 },
+            media: Limit { burst: 8, per_minute: 20 },
+            fetch: Limit { burst: 10, per_minute: 20 },
+        }
+    }
+}
+
+impl RateLimitSettings {
+    /// Limite d'un type de message aux portées session, IP et auteur
+    fn limit(&self, scope: Scope, kind: Kind) -> Limit {
+        match kind {
+            Kind::Post => match scope {
+                Scope::Session => self.session,
+                Scope::Ip => self.ip,
+                _ => self.author,
+            },
+            Kind::Reaction => self.reaction,
+            Kind::Follow => self.follow,
+            Kind::Profile => self.profile,
+            Kind::DirectMessage => self.dm,
+            Kind::Edit => self.edit,
+            Kind::Channel => self.channel,
+            Kind::Media => self.media,
+        }
+    }
+}
+
+/// Type de message signé, limité indépendamment des autres
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Post,
+    Reaction,
+    Follow,
+    Profile,
+    DirectMessage,
+    /// Modification ou suppression d'un post
+    Edit,
+    /// `join` d'un canal par une session WebSocket
+    Channel,
+    /// Envoi d'un blob
+    Media,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    Session,
+    Ip,
+    Author,
+    Peer,
+    Fetch,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Session => "session",
+            Scope::Ip => "ip",
+            Scope::Author => "author",
+            Scope::Peer => "peer",
+            Scope::Fetch => "fetch",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: Limit,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_minute as f64 / 60.0).min(self.limit.burst as f64);
+        self.last = now;
+    }
+
+    /// Consomme un jeton si disponible
+    pub fn try_take(&mut self) -> bool {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sans recharger le bucket : `last` reste la date de sa dernière utilisation
+    fn is_full(&self) -> bool {
+        let elapsed = self.last.elapsed().as_secs_f64();
+        self.tokens + elapsed * self.limit.per_minute as f64 / 60.0 >= self.limit.burst as f64
+    }
+}
+
+/// Un bucket par clé, créé avec la limite donnée à sa première utilisation
+struct KeyedLimiter<K> {
+    buckets: Mutex<HashMap<K, TokenBucket>>,
+}
+
+impl<K: Hash + Eq + Clone> KeyedLimiter<K> {
+    fn new() -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn try_take(&self, key: K, limit: Limit) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(&key) {
+            // Un bucket plein se recrée à l'identique : l'oublier ne change rien
+            buckets.retain(|_, bucket| !bucket.is_full());
+            if buckets.len() >= MAX_TRACKED_KEYS {
+                // Puis les moins récemment utilisés, un dixième d'un coup pour
+                // ne pas refaire ce tri à chaque nouvelle clé
+                let mut by_use: Vec<(Instant, K)> =
+                    buckets.iter().map(|(key, bucket)| (bucket.last, key.clone())).collect();
+                let evicted = buckets.len() - MAX_TRACKED_KEYS + MAX_TRACKED_KEYS / 10;
+                by_use.select_nth_unstable_by_key(evicted - 1, |(last, _)| *last);
+                for (_, key) in by_use.into_iter().take(evicted) {
+                    buckets.remove(&key);
+                }
+            }
+        }
+        buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(limit))
+            .try_take()
+    }
+}
+
+/// Buckets d'une session WebSocket, un par type de message
+pub struct SessionBuckets {
+    settings: RateLimitSettings,
+    buckets: HashMap<Kind, TokenBucket>,
+}
+
+impl SessionBuckets {
+    fn try_take(&mut self, kind: Kind) -> bool {
+        let limit = self.settings.limit(Scope::Session, kind);
+        self.buckets.entry(kind).or_insert_with(|| TokenBucket::new(limit)).try_take()
+    }
+}
+
+#[derive(Debug, Default)]
+struct RejectionCounters {
+    session: AtomicU64,
+    ip: AtomicU64,
+    author: AtomicU64,
+    peer: AtomicU64,
+    fetch: AtomicU64,
+}
+
+/// Limiteurs partagés par le serveur web et la boucle Gossipsub
+pub struct RateLimits {
+    pub settings: RateLimitSettings,
+    ip: KeyedLimiter<(Kind, IpAddr)>,
+    author: KeyedLimiter<(Kind, String)>,
+    peer: KeyedLimiter<PeerId>,
+    fetch: KeyedLimiter<IpAddr>,
+    rejected: RejectionCounters,
+}
+
+impl RateLimits {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            ip: KeyedLimiter::new(),
+            author: KeyedLimiter::new(),
+            peer: KeyedLimiter::new(),
+            fetch: KeyedLimiter::new(),
+            settings,
+            rejected: RejectionCounters::default(),
+        }
+    }
+
+    /// Buckets propres à une session WebSocket, détenus par la tâche de la session
+    pub fn session_buckets(&self) -> SessionBuckets {
+        SessionBuckets {
+            settings: self.settings.clone(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn check_ip(&self, ip: IpAddr, kind: Kind) -> Result<(), Scope> {
+        let limit = self.settings.limit(Scope::Ip, kind);
+        self.check(self.ip.try_take((kind, ip), limit), Scope::Ip)
+    }
+
+    pub fn check_author(&self, author: &str, kind: Kind) -> Result<(), Scope> {
+        let limit = self.settings.limit(Scope::Author, kind);
+        self.check(self.author.try_take((kind, author.to_string()), limit), Scope::Author)
+    }
+
+    pub fn check_peer(&self, peer: PeerId) -> Result<(), Scope> {
+        self.check(self.peer.try_take(peer, self.settings.peer), Scope::Peer)
+    }
+
+    pub fn check_fetch(&self, ip: IpAddr) -> Result<(), Scope> {
+        self.check(self.fetch.try_take(ip, self.settings.fetch), Scope::Fetch)
+    }
+
+    pub fn check_session(&self, buckets: &mut SessionBuckets, kind: Kind) -> Result<(), Scope> {
+        self.check(buckets.try_take(kind), Scope::Session)
+    }
+
+    fn check(&self, allowed: bool, scope: Scope) -> Result<(), Scope> {
+        if allowed {
+            return Ok(());
+        }
+        let counter = match scope {
+            Scope::Session => &self.rejected.session,
+            Scope::Ip => &self.rejected.ip,
+            Scope::Author => &self.rejected.author,
+            Scope::Peer => &self.rejected.peer,
+            Scope::Fetch => &self.rejected.fetch,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        Err(scope)
+    }
+
+    pub fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "session": self.rejected.session.load(Ordering::Relaxed),
+            "ip": self.rejected.ip.load(Ordering::Relaxed),
+            "author": self.rejected.author.load(Ordering::Relaxed),
+            "peer": self.rejected.peer.load(Ordering::Relaxed),
+            "fetch": self.rejected.fetch.load(Ordering::Relaxed),
+        })
+    }
+}
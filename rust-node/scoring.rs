@@ -0,0 +1,75 @@
+//! Score des peers Gossipsub
+//!
+//! Un peer gagne des points en étant le premier à livrer des messages valides
+//! et en restant dans le mesh ; il en perd pour chaque message invalide
+//! (pénalité au carré) et quand trop de peers partagent son IP. Sous
+//! `GRAYLIST_THRESHOLD`, ses messages sont ignorés sans être traités.
+//!
+//! Le débit du topic est faible et irrégulier : la pénalité pour trop peu de
+//! messages livrés dans le mesh (P3) est désactivée, elle sanctionnerait des
+//! relais honnêtes pendant les heures creuses.
+use libp2p::gossipsub::{score_parameter_decay, PeerScoreParams, PeerScoreThresholds, TopicHash, TopicScoreParams};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+/// Plus de gossip échangé en dessous
+pub const GOSSIP_THRESHOLD: f64 = -10.0;
+/// Nos propres messages ne lui sont plus publiés en dessous
+pub const PUBLISH_THRESHOLD: f64 = -50.0;
+/// Tous ses messages sont ignorés en dessous
+pub const GRAYLIST_THRESHOLD: f64 = -80.0;
+
+/// Poids d'un message invalide (compteur au carré) : un message coupe le
+/// gossip, trois (-180) graylistent même un peer au score maximal (+60)
+const INVALID_MESSAGE_WEIGHT: f64 = -20.0;
+/// Au-delà de ce nombre de peers derrière la même IP, pénalité au carré du surplus
+const IP_COLOCATION_THRESHOLD: f64 = 3.0;
+const IP_COLOCATION_WEIGHT: f64 = -10.0;
+
+pub fn thresholds() -> PeerScoreThresholds {
+    PeerScoreThresholds {
+        gossip_threshold: GOSSIP_THRESHOLD,
+        publish_threshold: PUBLISH_THRESHOLD,
+        graylist_threshold: GRAYLIST_THRESHOLD,
+        accept_px_threshold: 10.0,
+        opportunistic_graft_threshold: 5.0,
+    }
+}
+
+/// Paramètres d'un topic ; les canaux rejoints en cours de route reçoivent les
+/// mêmes que le topic principal
+pub fn topic_params() -> TopicScoreParams {
+    TopicScoreParams {
+        topic_weight: 1.0,
+        // P1 : +0.01 par seconde dans le mesh, plafonné à 10 minutes
+        time_in_mesh_weight: 0.01,
+        time_in_mesh_quantum: Duration::from_secs(1),
+        time_in_mesh_cap: 600.0,
+        // P2 : +1 par message livré en premier, oublié en une heure environ
+        first_message_deliveries_weight: 1.0,
+        first_message_deliveries_decay: score_parameter_decay(Duration::from_secs(3600)),
+        first_message_deliveries_cap: 50.0,
+        // P3 / P3b désactivés (voir l'en-tête du module)
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        // P4 : messages rejetés par `validation`, oubliés en une heure environ
+        invalid_message_deliveries_weight: INVALID_MESSAGE_WEIGHT,
+        invalid_message_deliveries_decay: score_parameter_decay(Duration::from_secs(3600)),
+        ..Default::default()
+    }
+}
+
+pub fn params(topics: impl IntoIterator<Item = TopicHash>) -> PeerScoreParams {
+    let mut params = PeerScoreParams {
+        topic_score_cap: 60.0,
+        ip_colocation_factor_weight: IP_COLOCATION_WEIGHT,
+        ip_colocation_factor_threshold: IP_COLOCATION_THRESHOLD,
+        // Plusieurs nœuds locaux (tests, mDNS) partagent l'adresse de loopback
+        ip_colocation_factor_whitelist: [IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    params.topics.extend(topics.into_iter().map(|topic| (topic, topic_params())));
+    params
+}
@@ -0,0 +1,334 @@
+//! Recherche plein texte
+//!
+//! Index inversé en mémoire, reconstruit depuis le stockage au démarrage puis
+//! tenu à jour par `add_post`, les modifications et les suppressions. Le texte
+//! indexé est le contenu courant d'un post (dernière modification de son
+//! auteur), découpé en mots sans casse ni accents.
+//!
+//! Syntaxe de `q` : `mot`, `"phrase exacte"`, `préfixe*`. Tous les termes
+//! doivent être présents ; les résultats sont classés par BM25, puis du plus
+//! récent au plus ancien.
+use crate::thread::TimelinePost;
+use crate::Post;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::RwLock;
+
+pub const MAX_QUERY_CHARS: usize = 200;
+pub const MAX_QUERY_TERMS: usize = 16;
+pub const DEFAULT_RESULTS: usize = 20;
+pub const MAX_RESULTS: usize = 100;
+/// Un préfixe plus court s'étendrait à presque tout le vocabulaire
+pub const MIN_PREFIX_CHARS: usize = 2;
+
+// Paramètres BM25 habituels
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+#[derive(Debug, Clone)]
+pub enum Term {
+    Word(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+#[derive(Debug)]
+pub enum QueryError {
+    Empty,
+    TooLong,
+    TooManyTerms,
+    PrefixTooShort,
+    BadRange,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Empty => write!(f, "requête vide : un terme ou un auteur est requis"),
+            QueryError::TooLong => write!(f, "requête trop longue (max {} caractères)", MAX_QUERY_CHARS),
+            QueryError::TooManyTerms => write!(f, "trop de termes (max {})", MAX_QUERY_TERMS),
+            QueryError::PrefixTooShort => write!(f, "préfixe trop court (min {} caractères)", MIN_PREFIX_CHARS),
+            QueryError::BadRange => write!(f, "intervalle de dates invalide"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Requête analysée, avec ses filtres
+#[derive(Debug, Default)]
+pub struct Query {
+    pub terms: Vec<Term>,
+    /// Clés d'auteurs acceptées ; vide pour tous les auteurs
+    pub authors: BTreeSet<String>,
+    /// Bornes incluses, en secondes Unix
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl Query {
+    pub fn parse(
+        text: &str,
+        authors: BTreeSet<String>,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<Self, QueryError> {
+        if text.chars().count() > MAX_QUERY_CHARS {
+            return Err(QueryError::TooLong);
+        }
+        if matches!((since, until), (Some(since), Some(until)) if since > until) {
+            return Err(QueryError::BadRange);
+        }
+
+        let mut terms = Vec::new();
+        // Les segments impairs sont entre guillemets
+        for (i, segment) in text.split('"').enumerate() {
+            if i % 2 == 1 {
+                let mut words = tokenize(segment);
+                match words.len() {
+                    0 => {}
+                    1 => terms.push(Term::Word(words.remove(0))),
+                    _ => terms.push(Term::Phrase(words)),
+                }
+                continue;
+            }
+            for chunk in segment.split_whitespace() {
+                let (stem, is_prefix) = match chunk.strip_suffix('*') {
+                    Some(stem) => (stem, true),
+                    None => (chunk, false),
+                };
+                let mut words = tokenize(stem);
+                let last = if is_prefix { words.pop() } else { None };
+                terms.extend(words.into_iter().map(Term::Word));
+                if let Some(prefix) = last {
+                    if prefix.chars().count() < MIN_PREFIX_CHARS {
+                        return Err(QueryError::PrefixTooShort);
+                    }
+                    terms.push(Term::Prefix(prefix));
+                }
+            }
+        }
+
+        if terms.len() > MAX_QUERY_TERMS {
+            return Err(QueryError::TooManyTerms);
+        }
+        if terms.is_empty() && authors.is_empty() {
+            return Err(QueryError::Empty);
+        }
+        Ok(Self { terms, authors, since, until })
+    }
+}
+
+/// Découpe un texte en mots indexables : minuscules, sans accents
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.chars().flat_map(char::to_lowercase).map(fold_accent).collect())
+        .collect()
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'â' | 'ä' | 'á' | 'ã' | 'å' => 'a',
+        'ç' => 'c',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'î' | 'ï' | 'í' | 'ì' => 'i',
+        'ñ' => 'n',
+        'ô' | 'ö' | 'ó' | 'ò' | 'õ' => 'o',
+        'ù' | 'û' | 'ü' | 'ú' => 'u',
+        'ÿ' | 'ý' => 'y',
+        _ => c,
+    }
+}
+
+struct Document {
+    author: String,
+    timestamp: i64,
+    /// Mots distincts, pour retirer le document de l'index
+    words: BTreeSet<String>,
+    len: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// mot -> id du post -> positions (croissantes)
+    postings: BTreeMap<String, HashMap<String, Vec<u32>>>,
+    docs: HashMap<String, Document>,
+    total_len: usize,
+}
+
+impl Inner {
+    fn remove(&mut self, id: &str) {
+        let Some(doc) = self.docs.remove(id) else {
+            return;
+        };
+        self.total_len -= doc.len;
+        for word in doc.words {
+            if let Some(posting) = self.postings.get_mut(&word) {
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    /// Occurrences d'un terme par document
+    fn matches(&self, term: &Term) -> HashMap<&str, usize> {
+        let mut counts = HashMap::new();
+        match term {
+            Term::Word(word) => {
+                if let Some(posting) = self.postings.get(word) {
+                    counts.extend(posting.iter().map(|(id, positions)| (id.as_str(), positions.len())));
+                }
+            }
+            Term::Prefix(prefix) => {
+                let words = self.postings.range(prefix.clone()..).take_while(|(word, _)| word.starts_with(prefix.as_str()));
+                for (_, posting) in words {
+                    for (id, positions) in posting {
+                        *counts.entry(id.as_str()).or_insert(0) += positions.len();
+                    }
+                }
+            }
+            Term::Phrase(words) => {
+                let postings: Option<Vec<_>> = words.iter().map(|word| self.postings.get(word)).collect();
+                let Some(postings) = postings else {
+                    return counts;
+                };
+                for (id, starts) in postings[0] {
+                    let occurrences = starts
+                        .iter()
+                        .filter(|&&start| {
+                            postings[1..].iter().enumerate().all(|(offset, posting)| {
+                                posting
+                                    .get(id)
+                                    .is_some_and(|positions| positions.binary_search(&(start + offset as u32 + 1)).is_ok())
+                            })
+                        })
+                        .count();
+                    if occurrences > 0 {
+                        counts.insert(id.as_str(), occurrences);
+                    }
+                }
+            }
+        }
+        counts
+    }
+}
+
+/// Résultat classé
+#[derive(Debug)]
+pub struct Hit {
+    pub id: String,
+    pub score: f64,
+}
+
+/// Réponse de `/api/search`
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    /// Nombre de posts correspondants, avant la limite
+    pub total: usize,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub score: f64,
+    #[serde(flatten)]
+    pub post: TimelinePost,
+}
+
+pub struct SearchIndex {
+    inner: RwLock<Inner>,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self { inner: RwLock::new(Inner::default()) }
+    }
+
+    /// Indexe le contenu courant d'un post et le texte alternatif de ses
+    /// pièces jointes, en remplaçant la version précédente
+    pub fn index(&self, post: &Post) {
+        let mut words = tokenize(&post.content);
+        for alt in post.attachments.iter().filter_map(|attachment| attachment.alt.as_deref()) {
+            words.extend(tokenize(alt));
+        }
+        let mut inner = self.inner.write().unwrap();
+        inner.remove(&post.id);
+        for (position, word) in words.iter().enumerate() {
+            inner
+                .postings
+                .entry(word.clone())
+                .or_default()
+                .entry(post.id.clone())
+                .or_default()
+                .push(position as u32);
+        }
+        inner.total_len += words.len();
+        inner.docs.insert(
+            post.id.clone(),
+            Document {
+                author: post.author.clone(),
+                timestamp: post.timestamp,
+                len: words.len(),
+                words: words.into_iter().collect(),
+            },
+        );
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.inner.write().unwrap().remove(id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().docs.len()
+    }
+
+    /// Tous les posts correspondant à la requête, du plus pertinent au moins pertinent
+    pub fn search(&self, query: &Query) -> Vec<Hit> {
+        let inner = self.inner.read().unwrap();
+        let accepts = |doc: &Document| {
+            (query.authors.is_empty() || query.authors.contains(&doc.author))
+                && query.since.is_none_or(|since| doc.timestamp >= since)
+                && query.until.is_none_or(|until| doc.timestamp <= until)
+        };
+
+        let per_term: Vec<HashMap<&str, usize>> = query.terms.iter().map(|term| inner.matches(term)).collect();
+        let candidates: Vec<(&str, &Document)> = match per_term.iter().min_by_key(|counts| counts.len()) {
+            Some(rarest) => rarest
+                .keys()
+                .filter(|id| per_term.iter().all(|counts| counts.contains_key(*id)))
+                .filter_map(|id| inner.docs.get(*id).map(|doc| (*id, doc)))
+                .filter(|(_, doc)| accepts(doc))
+                .collect(),
+            // Filtres seuls : tous les posts retenus, du plus récent au plus ancien
+            None => inner.docs.iter().map(|(id, doc)| (id.as_str(), doc)).filter(|(_, doc)| accepts(doc)).collect(),
+        };
+
+        let total = inner.docs.len() as f64;
+        let average_len = (inner.total_len as f64 / total.max(1.0)).max(1.0);
+        let mut hits: Vec<(Hit, i64)> = candidates
+            .into_iter()
+            .map(|(id, doc)| {
+                let norm = K1 * (1.0 - B + B * doc.len as f64 / average_len);
+                let score = per_term.iter().fold(0.0, |score, counts| {
+                    let frequency = counts.len() as f64;
+                    let idf = (1.0 + (total - frequency + 0.5) / (frequency + 0.5)).ln();
+                    let tf = counts[id] as f64;
+                    score + idf * tf * (K1 + 1.0) / (tf + norm)
+                });
+                (Hit { id: id.to_string(), score }, doc.timestamp)
+            })
+            .collect();
+        hits.sort_by(|(a, a_time), (b, b_time)| b.score.total_cmp(&a.score).then(b_time.cmp(a_time)));
+        hits.into_iter().map(|(hit, _)| hit).collect()
+    }
+}
@@ -0,0 +1,307 @@
+//! Signatures Ed25519 des posts, réactions, modifications, suppressions,
+//! profils, abonnements et messages privés
+//!
+//! `MessageAuthenticity::Signed` de Gossipsub prouve seulement quel relais a
+//! transmis un message. Chaque `Post` porte donc la clé publique de son auteur
+//! (`author`, en hex) et une signature sur un encodage canonique de ses champs.
+use crate::{Delete, DirectMessage, Edit, Follow, Post, Profile, Reaction};
+use libp2p::identity::ed25519;
+
+/// Préfixe de domaine pour ne jamais confondre une signature de post avec
+/// une autre signature produite par la même clé
+const POST_DOMAIN: &str = "zeta-post-v1";
+const AUTH_DOMAIN: &str = "zeta-auth-v1";
+const REACTION_DOMAIN: &str = "zeta-reaction-v1";
+const EDIT_DOMAIN: &str = "zeta-edit-v1";
+const DELETE_DOMAIN: &str = "zeta-delete-v1";
+const PROFILE_DOMAIN: &str = "zeta-profile-v1";
+/// Domaines distincts : un abonnement signé ne peut pas être rejoué en désabonnement
+const FOLLOW_DOMAIN: &str = "zeta-follow-v1";
+const UNFOLLOW_DOMAIN: &str = "zeta-unfollow-v1";
+const DM_DOMAIN: &str = "zeta-dm-v1";
+
+/// Encodage canonique d'un post : un tableau JSON des champs signés.
+///
+/// Les navigateurs reproduisent exactement ces octets avec
+/// `JSON.stringify(["zeta-post-v1", id, author, author_name, content, timestamp])`,
+/// suivi de `reply_to, root` pour une réponse, un post de canal ou avec
+/// pièces jointes, puis de `channel` pour ces deux derniers, puis des pièces
+/// jointes `[[hash, mime, size, name, alt], ...]` (les posts simples gardent
+/// l'encodage d'origine).
+pub fn canonical_bytes(post: &Post) -> Vec<u8> {
+    let mut fields = vec![
+        serde_json::json!(POST_DOMAIN),
+        serde_json::json!(post.id),
+        serde_json::json!(post.author),
+        serde_json::json!(post.author_name),
+        serde_json::json!(post.content),
+        serde_json::json!(post.timestamp),
+    ];
+    let has_attachments = !post.attachments.is_empty();
+    if post.reply_to.is_some() || post.root.is_some() || post.channel.is_some() || has_attachments {
+        fields.push(serde_json::json!(post.reply_to));
+        fields.push(serde_json::json!(post.root));
+    }
+    if post.channel.is_some() || has_attachments {
+        fields.push(serde_json::json!(post.channel));
+    }
+    // Tableaux plutôt qu'objets : l'ordre des champs ne dépend pas du sérialiseur
+    if has_attachments {
+        let attachments: Vec<_> = post
+            .attachments
+            .iter()
+            .map(|a| serde_json::json!([a.hash, a.mime, a.size, a.name, a.alt]))
+            .collect();
+        fields.push(serde_json::Value::Array(attachments));
+    }
+    serde_json::Value::Array(fields).to_string().into_bytes()
+}
+
+/// Encodage canonique d'une réaction :
+/// `JSON.stringify(["zeta-reaction-v1", post_id, author, reaction, timestamp])`
+pub fn reaction_bytes(reaction: &Reaction) -> Vec<u8> {
+    serde_json::json!([
+        REACTION_DOMAIN,
+        reaction.post_id,
+        reaction.author,
+        reaction.reaction,
+        reaction.timestamp,
+    ])
+    .to_string()
+    .into_bytes()
+}
+
+/// `JSON.stringify(["zeta-edit-v1", post_id, author, content, timestamp])`
+pub fn edit_bytes(edit: &Edit) -> Vec<u8> {
+    serde_json::json!([EDIT_DOMAIN, edit.post_id, edit.author, edit.content, edit.timestamp])
+        .to_string()
+        .into_bytes()
+}
+
+/// `JSON.stringify(["zeta-delete-v1", post_id, author, timestamp])`
+pub fn delete_bytes(delete: &Delete) -> Vec<u8> {
+    serde_json::json!([DELETE_DOMAIN, delete.post_id, delete.author, delete.timestamp])
+        .to_string()
+        .into_bytes()
+}
+
+/// `JSON.stringify(["zeta-profile-v1", author, display_name, bio, avatar, timestamp])`,
+/// `avatar` valant `null` en l'absence d'avatar, suivi de `dm_key` quand le
+/// profil en publie une : la clé de chiffrement est ainsi liée à l'identité
+pub fn profile_bytes(profile: &Profile) -> Vec<u8> {
+    let mut fields = vec![
+        serde_json::json!(PROFILE_DOMAIN),
+        serde_json::json!(profile.author),
+        serde_json::json!(profile.display_name),
+        serde_json::json!(profile.bio),
+        serde_json::json!(profile.avatar),
+        serde_json::json!(profile.timestamp),
+    ];
+    if let Some(dm_key) = &profile.dm_key {
+        fields.push(serde_json::json!(dm_key));
+    }
+    serde_json::Value::Array(fields).to_string().into_bytes()
+}
+
+/// `JSON.stringify(["zeta-dm-v1", id, sender, recipient, sender_dm_key,
+/// recipient_dm_key, nonce, ciphertext, timestamp])`
+pub fn direct_message_bytes(dm: &DirectMessage) -> Vec<u8> {
+    serde_json::json!([
+        DM_DOMAIN,
+        dm.id,
+        dm.sender,
+        dm.recipient,
+        dm.sender_dm_key,
+        dm.recipient_dm_key,
+        dm.nonce,
+        dm.ciphertext,
+        dm.timestamp,
+    ])
+    .to_string()
+    .into_bytes()
+}
+
+/// `JSON.stringify(["zeta-follow-v1", author, target, timestamp])`, ou
+/// `"zeta-unfollow-v1"` pour un désabonnement
+pub fn follow_bytes(follow: &Follow, following: bool) -> Vec<u8> {
+    let domain = if following { FOLLOW_DOMAIN } else { UNFOLLOW_DOMAIN };
+    serde_json::json!([domain, follow.author, follow.target, follow.timestamp])
+        .to_string()
+        .into_bytes()
+}
+
+/// Octets signés par un navigateur pour répondre au challenge de `/ws`.
+///
+/// Le Peer ID du relais est inclus pour qu'une réponse ne puisse pas être
+/// rejouée auprès d'un autre relais.
+pub fn auth_challenge_bytes(challenge: &str, relay_peer_id: &str) -> Vec<u8> {
+    serde_json::json!([AUTH_DOMAIN, challenge, relay_peer_id])
+        .to_string()
+        .into_bytes()
+}
+
+/// Clé publique d'une paire Ed25519, encodée en hex
+pub fn public_key_hex(keypair: &ed25519::Keypair) -> String {
+    hex::encode(keypair.public().to_bytes())
+}
+
+/// Renseigne `author` et `signature` avec la clé donnée
+pub fn sign_post(post: &mut Post, keypair: &ed25519::Keypair) {
+    post.author = public_key_hex(keypair);
+    post.signature = hex::encode(keypair.sign(&canonical_bytes(post)));
+}
+
+pub fn sign_reaction(reaction: &mut Reaction, keypair: &ed25519::Keypair) {
+    reaction.author = public_key_hex(keypair);
+    reaction.signature = hex::encode(keypair.sign(&reaction_bytes(reaction)));
+}
+
+pub fn sign_edit(edit: &mut Edit, keypair: &ed25519::Keypair) {
+    edit.author = public_key_hex(keypair);
+    edit.signature = hex::encode(keypair.sign(&edit_bytes(edit)));
+}
+
+pub fn sign_delete(delete: &mut Delete, keypair: &ed25519::Keypair) {
+    delete.author = public_key_hex(keypair);
+    delete.signature = hex::encode(keypair.sign(&delete_bytes(delete)));
+}
+
+pub fn sign_profile(profile: &mut Profile, keypair: &ed25519::Keypair) {
+    profile.author = public_key_hex(keypair);
+    profile.signature = hex::encode(keypair.sign(&profile_bytes(profile)));
+}
+
+/// Vérifie qu'une signature hex a été produite par la clé publique hex `author`
+pub fn verify_hex(author: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(key_bytes) = hex::decode(author) else {
+        return false;
+    };
+    let Ok(public_key) = ed25519::PublicKey::try_from_bytes(&key_bytes) else {
+        return false;
+    };
+    let Ok(sig_bytes) = hex::decode(signature) else {
+        return false;
+    };
+    public_key.verify(message, &sig_bytes)
+}
+
+/// Vérifie que `post.signature` a bien été produite par la clé `post.author`
+pub fn verify_post(post: &Post) -> bool {
+    verify_hex(&post.author, &canonical_bytes(post), &post.signature)
+}
+
+pub fn verify_reaction(reaction: &Reaction) -> bool {
+    verify_hex(&reaction.author, &reaction_bytes(reaction), &reaction.signature)
+}
+
+pub fn verify_edit(edit: &Edit) -> bool {
+    verify_hex(&edit.author, &edit_bytes(edit), &edit.signature)
+}
+
+pub fn verify_delete(delete: &Delete) -> bool {
+    verify_hex(&delete.author, &delete_bytes(delete), &delete.signature)
+}
+
+pub fn sign_follow(follow: &mut Follow, following: bool, keypair: &ed25519::Keypair) {
+    follow.author = public_key_hex(keypair);
+    follow.signature = hex::encode(keypair.sign(&follow_bytes(follow, following)));
+}
+
+pub fn verify_follow(follow: &Follow, following: bool) -> bool {
+    verify_hex(&follow.author, &follow_bytes(follow, following), &follow.signature)
+}
+
+pub fn verify_direct_message(dm: &DirectMessage) -> bool {
+    verify_hex(&dm.sender, &direct_message_bytes(dm), &dm.signature)
+}
+
+pub fn verify_profile(profile: &Profile) -> bool {
+    verify_hex(&profile.author, &profile_bytes(profile), &profile.signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::Attachment;
+
+    // Octets attendus de `signPost` (static/index.html) pour les mêmes posts
+    fn post() -> Post {
+        Post {
+            id: "3f2c8a1e-0000-4000-8000-000000000001".into(),
+            author: "a1".repeat(32),
+            author_name: "Zoé".into(),
+            content: "Salut \"tout\" le monde\n".into(),
+            timestamp: 1_700_000_000,
+            reply_to: None,
+            root: None,
+            channel: None,
+            attachments: Vec::new(),
+            signature: String::new(),
+        }
+    }
+
+    fn canonical(post: &Post) -> String {
+        String::from_utf8(canonical_bytes(post)).unwrap()
+    }
+
+    const PREFIX: &str = r#"["zeta-post-v1","3f2c8a1e-0000-4000-8000-000000000001","a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1","Zoé","Salut \"tout\" le monde\n",1700000000"#;
+
+    #[test]
+    fn plain_post() {
+        assert_eq!(canonical(&post()), format!("{}]", PREFIX));
+    }
+
+    #[test]
+    fn reply() {
+        let post = Post {
+            reply_to: Some("3f2c8a1e-0000-4000-8000-000000000002".into()),
+            root: Some("3f2c8a1e-0000-4000-8000-000000000003".into()),
+            ..post()
+        };
+        assert_eq!(
+            canonical(&post),
+            format!(
+                r#"{},"3f2c8a1e-0000-4000-8000-000000000002","3f2c8a1e-0000-4000-8000-000000000003"]"#,
+                PREFIX
+            )
+        );
+    }
+
+    #[test]
+    fn channel_post() {
+        let post = Post { channel: Some("rust".into()), ..post() };
+        assert_eq!(canonical(&post), format!(r#"{},null,null,"rust"]"#, PREFIX));
+    }
+
+    #[test]
+    fn post_with_attachments() {
+        let post = Post {
+            attachments: vec![
+                Attachment {
+                    hash: "0f".repeat(32),
+                    mime: "image/png".into(),
+                    size: 2048,
+                    name: Some("chat.png".into()),
+                    alt: Some(String::new()),
+                },
+                Attachment {
+                    hash: "e3".repeat(32),
+                    mime: "image/jpeg".into(),
+                    size: 512,
+                    name: None,
+                    alt: Some("Un chat".into()),
+                },
+            ],
+            ..post()
+        };
+        assert_eq!(
+            canonical(&post),
+            format!(
+                r#"{},null,null,null,[["{}","image/png",2048,"chat.png",""],["{}","image/jpeg",512,null,"Un chat"]]]"#,
+                PREFIX,
+                "0f".repeat(32),
+                "e3".repeat(32)
+            )
+        );
+    }
+}
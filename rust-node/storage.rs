@@ -0,0 +1,840 @@
+//! Stockage persistant des posts
+//!
+//! Les posts survivent aux redémarrages du relais. `SledStore` garde un arbre
+//! principal indexé par id, des index secondaires triés par timestamp (global,
+//! par auteur et par fil de discussion) pour servir la timeline sans tout
+//! charger en mémoire, le nombre de réponses directes de chaque post, la
+//! dernière réaction de chaque auteur à chaque post, l'historique des
+//! modifications et les tombstones des posts supprimés par leur auteur.
+//! Une réponse peut être stockée avant son parent : les index ne portent que
+//! des ids. Réactions et modifications ne sont acceptées que pour un post
+//! stocké, et partent avec lui.
+//!
+//! Rétention : au-delà de `max_posts`, les posts les plus anciens sont retirés
+//! (avec leurs réactions et modifications) et un post plus ancien que tous
+//! ceux conservés est refusé. Les tombstones suivent la même borne, les plus
+//! anciennes suppressions oubliées en premier.
+use crate::{Delete, DirectMessage, Edit, Follow, Post, Profile, Reaction};
+use serde::{Deserialize, Serialize};
+use sled::transaction::{ConflictableTransactionError, ConflictableTransactionResult, TransactionError, TransactionalTree};
+use sled::Transactional;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::str::FromStr;
+
+pub type StorageError = Box<dyn std::error::Error + Send + Sync>;
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// Backend de stockage des posts
+pub trait PostStore: Send + Sync {
+    /// Insère un post ; renvoie `false` s'il existait déjà (même id) ou si
+    /// son auteur l'a supprimé
+    fn insert(&self, post: &Post) -> StorageResult<bool>;
+
+    fn get(&self, id: &str) -> StorageResult<Option<Post>>;
+
+    /// Supprime un post, ses entrées d'index, ses réactions et ses
+    /// modifications ; renvoie le post supprimé
+    fn remove(&self, id: &str) -> StorageResult<Option<Post>>;
+
+    /// Les `limit` posts les plus récents, du plus récent au plus ancien
+    fn latest(&self, limit: usize) -> StorageResult<Vec<Post>>;
+
+    /// Les posts postérieurs à `(timestamp, after_id)`, du plus ancien au plus
+    /// récent ; sans `after_id`, le timestamp lui-même est inclus
+    fn after(&self, timestamp: i64, after_id: Option<&str>, limit: usize) -> StorageResult<Vec<Post>>;
+
+    /// Les `limit` posts les plus récents d'un auteur
+    fn by_author(&self, author: &str, limit: usize) -> StorageResult<Vec<Post>>;
+
+    /// Les `limit` posts les plus récents d'un canal
+    fn by_channel(&self, channel: &str, limit: usize) -> StorageResult<Vec<Post>>;
+
+    /// Page de la timeline globale, ou d'un auteur, du plus récent au plus
+    /// ancien : les `limit` posts juste avant `before`, ou à défaut juste
+    /// après `after` ; les deux curseurs sont exclus
+    fn page(
+        &self,
+        author: Option<&str>,
+        before: Option<&Cursor>,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> StorageResult<Vec<Post>>;
+
+    /// Les réponses d'un fil (posts dont `root` vaut `root`), du plus ancien au plus récent
+    fn thread(&self, root: &str, limit: usize) -> StorageResult<Vec<Post>>;
+
+    /// Nombre de réponses directes reçues par un post, présent ou non
+    fn reply_count(&self, id: &str) -> StorageResult<u64>;
+
+    /// Enregistre une réaction à un post stocké si elle est plus récente que
+    /// celle du même auteur au même post ; renvoie `false` sinon
+    fn set_reaction(&self, reaction: &Reaction) -> StorageResult<bool>;
+
+    /// Nombre de réactions d'un post, par réaction
+    fn reaction_counts(&self, post_id: &str) -> StorageResult<BTreeMap<String, u64>>;
+
+    /// Enregistre une modification d'un post stocké ; renvoie `false` si elle
+    /// était déjà connue ou si le post est absent
+    fn add_edit(&self, edit: &Edit) -> StorageResult<bool>;
+
+    /// Modifications reçues pour un post, de la plus ancienne à la plus récente,
+    /// quel qu'en soit le signataire
+    fn edits(&self, post_id: &str) -> StorageResult<Vec<Edit>>;
+
+    /// Enregistre la tombstone, puis retire le post (avec ses modifications et
+    /// réactions) s'il est présent et appartient au signataire
+    fn delete(&self, delete: &Delete) -> StorageResult<Option<Post>>;
+
+    fn tombstone(&self, post_id: &str, author: &str) -> StorageResult<Option<Delete>>;
+
+    /// Suppressions datées de `from` (inclus) à `until` (exclu, sans borne si
+    /// `None`), de la plus ancienne à la plus récente
+    fn tombstones_between(&self, from: i64, until: Option<i64>, limit: usize) -> StorageResult<Vec<Delete>>;
+
+    /// Retire les posts les plus anciens au-delà de la limite de rétention,
+    /// avec leurs réactions et modifications ; renvoie les posts retirés
+    fn evict_oldest(&self) -> StorageResult<Vec<Post>>;
+
+    /// Enregistre un profil s'il est plus récent que celui connu pour son
+    /// auteur ; renvoie `false` sinon
+    fn set_profile(&self, profile: &Profile) -> StorageResult<bool>;
+
+    fn profile(&self, author: &str) -> StorageResult<Option<Profile>>;
+
+    /// Enregistre un abonnement (`following`) ou un désabonnement s'il est
+    /// plus récent que le dernier connu pour la même paire ; renvoie `false` sinon
+    fn set_follow(&self, follow: &Follow, following: bool) -> StorageResult<bool>;
+
+    /// Clés des auteurs suivis par `author`
+    fn following(&self, author: &str) -> StorageResult<Vec<String>>;
+
+    /// Enregistre un message privé ; renvoie `false` s'il était déjà connu
+    fn insert_dm(&self, dm: &DirectMessage) -> StorageResult<bool>;
+
+    /// Messages reçus par `recipient` et pas encore livrés, du plus ancien au plus récent
+    fn pending_dms(&self, recipient: &str, limit: usize) -> StorageResult<Vec<DirectMessage>>;
+
+    /// Retire ce message de ceux en attente de son destinataire
+    fn mark_delivered(&self, dm: &DirectMessage) -> StorageResult<()>;
+
+    /// Conversations de `author`, de la plus récente à la plus ancienne
+    fn conversations(&self, author: &str) -> StorageResult<Vec<Conversation>>;
+
+    /// Les `limit` derniers messages échangés entre `author` et `peer`, du plus ancien au plus récent
+    fn conversation(&self, author: &str, peer: &str, limit: usize) -> StorageResult<Vec<DirectMessage>>;
+
+    /// Nombre de posts stockés, tenu à jour sans parcourir la base
+    fn len(&self) -> usize;
+
+    /// Taille de la base sur disque, en octets
+    fn size_on_disk(&self) -> StorageResult<u64>;
+
+    /// Écrit une clé témoin jusqu'au disque, pour vérifier que le stockage accepte les écritures
+    fn probe(&self) -> StorageResult<()>;
+}
+
+/// Stockage embarqué sur disque basé sur sled
+pub struct SledStore {
+    db: sled::Db,
+    /// Nombre d'entrées de `posts`, compté une fois à l'ouverture
+    post_count: AtomicUsize,
+    /// Posts conservés, et tombstones gardées
+    max_posts: usize,
+    /// Nombre d'entrées de `tombstones`, compté une fois à l'ouverture
+    tombstone_count: AtomicUsize,
+    /// id -> post JSON
+    posts: sled::Tree,
+    /// timestamp ++ id -> id
+    by_time: sled::Tree,
+    /// author ++ 0x00 ++ timestamp ++ id -> id
+    by_author: sled::Tree,
+    /// root ++ 0x00 ++ timestamp ++ id -> id
+    by_root: sled::Tree,
+    /// channel ++ 0x00 ++ timestamp ++ id -> id
+    by_channel: sled::Tree,
+    /// id du parent -> nombre de réponses directes (u64 big-endian)
+    reply_counts: sled::Tree,
+    /// post_id ++ 0x00 ++ author -> réaction JSON
+    reactions: sled::Tree,
+    /// post_id ++ 0x00 ++ timestamp ++ signature -> modification JSON
+    edits: sled::Tree,
+    /// post_id ++ 0x00 ++ author -> suppression JSON ; indexé par auteur pour
+    /// qu'une suppression forgée arrivée avant le post ne masque pas la vraie
+    tombstones: sled::Tree,
+    /// timestamp de la suppression ++ post_id ++ 0x00 ++ author -> clé de la
+    /// tombstone, pour oublier les plus anciennes
+    tombstones_by_time: sled::Tree,
+    /// author -> profil JSON le plus récent
+    profiles: sled::Tree,
+    /// author ++ 0x00 ++ target -> dernier abonnement ou désabonnement JSON
+    follows: sled::Tree,
+    /// id -> message privé JSON
+    dms: sled::Tree,
+    /// participant ++ 0x00 ++ autre participant ++ 0x00 ++ timestamp ++ id -> id,
+    /// une entrée pour chacun des deux participants
+    dms_by_conversation: sled::Tree,
+    /// recipient ++ 0x00 ++ timestamp ++ id -> id, pour les seuls messages pas
+    /// encore livrés : chaque message est retiré à sa livraison, quel que soit
+    /// l'ordre d'arrivée
+    dms_inbox: sled::Tree,
+}
+
+/// Résumé d'une conversation privée, vue par l'un des participants
+#[derive(Debug, Serialize)]
+pub struct Conversation {
+    pub peer: String,
+    pub messages: u64,
+    pub last_timestamp: i64,
+}
+
+/// Position d'un post dans la timeline, sous la forme `<timestamp>:<id>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub timestamp: i64,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn of(post: &Post) -> Self {
+        Self { timestamp: post.timestamp, id: post.id.clone() }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.timestamp, self.id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (timestamp, id) = s.split_once(':').ok_or("curseur invalide (attendu <timestamp>:<id>)")?;
+        let timestamp = timestamp.parse().map_err(|_| "timestamp du curseur invalide")?;
+        Ok(Self { timestamp, id: id.to_string() })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Dernière action connue d'un auteur envers un autre
+#[derive(Serialize, Deserialize)]
+struct FollowEntry {
+    following: bool,
+    follow: Follow,
+}
+
+impl SledStore {
+    pub fn open(path: impl AsRef<Path>, max_posts: usize) -> StorageResult<Self> {
+        let db = sled::open(path)?;
+        let posts = db.open_tree("posts")?;
+        let tombstones = db.open_tree("tombstones")?;
+        Ok(Self {
+            post_count: AtomicUsize::new(posts.len()),
+            max_posts,
+            tombstone_count: AtomicUsize::new(tombstones.len()),
+            posts,
+            by_time: db.open_tree("posts_by_time")?,
+            by_author: db.open_tree("posts_by_author")?,
+            by_root: db.open_tree("posts_by_root")?,
+            by_channel: db.open_tree("posts_by_channel")?,
+            reply_counts: db.open_tree("reply_counts")?,
+            reactions: db.open_tree("reactions")?,
+            edits: db.open_tree("edits")?,
+            tombstones,
+            tombstones_by_time: db.open_tree("tombstones_by_time")?,
+            profiles: db.open_tree("profiles")?,
+            follows: db.open_tree("follows")?,
+            dms: db.open_tree("dms")?,
+            dms_by_conversation: db.open_tree("dms_by_conversation")?,
+            dms_inbox: db.open_tree("dms_inbox")?,
+            db,
+        })
+    }
+
+    /// Charge les posts référencés par un index, dans l'ordre donné
+    fn load_ids<I>(&self, ids: I, limit: usize) -> StorageResult<Vec<Post>>
+    where
+        I: Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>>,
+    {
+        let mut result = Vec::new();
+        for entry in ids.take(limit) {
+            let (_, id) = entry?;
+            if let Some(bytes) = self.posts.get(&id)? {
+                result.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Clés des modifications et des réactions d'un post, relevées avant une
+    /// transaction qui les retire (les transactions sled ne parcourent pas les préfixes)
+    fn child_keys(&self, post_id: &str) -> StorageResult<(Vec<sled::IVec>, Vec<sled::IVec>)> {
+        let prefix = author_prefix(post_id);
+        let edit_keys = self.edits.scan_prefix(&prefix).keys().collect::<sled::Result<_>>()?;
+        let reaction_keys = self.reactions.scan_prefix(&prefix).keys().collect::<sled::Result<_>>()?;
+        Ok((edit_keys, reaction_keys))
+    }
+
+    /// Retire les modifications et réactions enregistrées entre `child_keys`
+    /// et le retrait du post ; une fois le post absent, plus aucune n'est acceptée
+    fn sweep_children(&self, post_id: &str) -> StorageResult<()> {
+        let (edit_keys, reaction_keys) = self.child_keys(post_id)?;
+        for key in edit_keys {
+            self.edits.remove(key)?;
+        }
+        for key in reaction_keys {
+            self.reactions.remove(key)?;
+        }
+        Ok(())
+    }
+
+    /// Oublie les tombstones les plus anciennes tant qu'elles dépassent `max_posts`
+    fn trim_tombstones(&self) -> StorageResult<()> {
+        while self.tombstone_count.load(Ordering::Relaxed) > self.max_posts {
+            let Some((_, key)) = self.tombstones_by_time.pop_min()? else {
+                break;
+            };
+            if self.tombstones.remove(key)?.is_some() {
+                self.tombstone_count.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Clé d'index triable : le timestamp en big-endian (bit de signe inversé
+/// pour que les valeurs négatives restent ordonnées) suivi de l'id
+fn time_key(timestamp: i64, id: &str) -> Vec<u8> {
+    let mut key = ((timestamp as u64) ^ (1 << 63)).to_be_bytes().to_vec();
+    key.extend_from_slice(id.as_bytes());
+    key
+}
+
+/// Préfixe `<valeur> ++ 0x00` des index par auteur et par fil
+fn author_prefix(author: &str) -> Vec<u8> {
+    let mut prefix = author.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn prefixed_key(prefix: &str, time_key: &[u8]) -> Vec<u8> {
+    let mut key = author_prefix(prefix);
+    key.extend_from_slice(time_key);
+    key
+}
+
+fn decode_count(bytes: Option<sled::IVec>) -> u64 {
+    bytes
+        .and_then(|b| <[u8; 8]>::try_from(b.as_ref()).ok())
+        .map(u64::from_be_bytes)
+        .unwrap_or(0)
+}
+
+fn storage_error(e: TransactionError<()>) -> StorageError {
+    match e {
+        TransactionError::Storage(e) => StorageError::from(e),
+        TransactionError::Abort(()) => "transaction annulée".into(),
+    }
+}
+
+/// Retire un post et ses entrées d'index, dans une transaction ouverte sur
+/// `posts`, `by_time`, `by_author`, `by_root`, `by_channel` et `reply_counts`
+fn unlink(post: &Post, trees: [&TransactionalTree; 6]) -> ConflictableTransactionResult<(), ()> {
+    let [posts, by_time, by_author, by_root, by_channel, reply_counts] = trees;
+    let time_key = time_key(post.timestamp, &post.id);
+    posts.remove(post.id.as_bytes())?;
+    by_time.remove(time_key.as_slice())?;
+    by_author.remove(prefixed_key(&post.author, &time_key))?;
+    if let Some(root) = &post.root {
+        by_root.remove(prefixed_key(root, &time_key))?;
+    }
+    if let Some(channel) = &post.channel {
+        by_channel.remove(prefixed_key(channel, &time_key))?;
+    }
+    if let Some(parent) = &post.reply_to {
+        let count = decode_count(reply_counts.get(parent.as_bytes())?);
+        reply_counts.insert(parent.as_bytes(), &count.saturating_sub(1).to_be_bytes())?;
+    }
+    Ok(())
+}
+
+impl PostStore for SledStore {
+    fn insert(&self, post: &Post) -> StorageResult<bool> {
+        let json = serde_json::to_vec(post)?;
+        let time_key = time_key(post.timestamp, &post.id);
+        let author_key = prefixed_key(&post.author, &time_key);
+        let root_key = post.root.as_deref().map(|root| prefixed_key(root, &time_key));
+        let channel_key = post.channel.as_deref().map(|channel| prefixed_key(channel, &time_key));
+        let tombstone_key = prefixed_key(&post.id, post.author.as_bytes());
+        // Stockage plein : un post plus ancien que tous ceux conservés serait le premier retiré
+        if self.len() >= self.max_posts
+            && self.by_time.first()?.is_some_and(|(oldest, _)| time_key.as_slice() < oldest.as_ref())
+        {
+            return Ok(false);
+        }
+
+        let trees = (
+            &self.posts,
+            &self.by_time,
+            &self.by_author,
+            &self.by_root,
+            &self.by_channel,
+            &self.reply_counts,
+            &self.tombstones,
+        );
+        let inserted = trees
+            .transaction(|(posts, by_time, by_author, by_root, by_channel, reply_counts, tombstones)| {
+                if posts.get(post.id.as_bytes())?.is_some() || tombstones.get(tombstone_key.as_slice())?.is_some() {
+                    return Ok(false);
+                }
+                posts.insert(post.id.as_bytes(), json.as_slice())?;
+                by_time.insert(time_key.as_slice(), post.id.as_bytes())?;
+                by_author.insert(author_key.as_slice(), post.id.as_bytes())?;
+                if let Some(root_key) = &root_key {
+                    by_root.insert(root_key.as_slice(), post.id.as_bytes())?;
+                }
+                if let Some(channel_key) = &channel_key {
+                    by_channel.insert(channel_key.as_slice(), post.id.as_bytes())?;
+                }
+                if let Some(parent) = &post.reply_to {
+                    let count = decode_count(reply_counts.get(parent.as_bytes())?);
+                    reply_counts.insert(parent.as_bytes(), &(count + 1).to_be_bytes())?;
+                }
+                Ok::<_, ConflictableTransactionError<()>>(true)
+            })
+            .map_err(storage_error)?;
+        if inserted {
+            self.post_count.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(inserted)
+    }
+
+    fn remove(&self, id: &str) -> StorageResult<Option<Post>> {
+        let Some(post) = self.get(id)? else {
+            return Ok(None);
+        };
+        let (edit_keys, reaction_keys) = self.child_keys(id)?;
+        let trees = (
+            &self.posts,
+            &self.by_time,
+            &self.by_author,
+            &self.by_root,
+            &self.by_channel,
+            &self.reply_counts,
+            &self.edits,
+            &self.reactions,
+        );
+        let removed = trees
+            .transaction(|(posts, by_time, by_author, by_root, by_channel, reply_counts, edits, reactions)| {
+                // Déjà retiré par un appel concurrent
+                if posts.get(post.id.as_bytes())?.is_none() {
+                    return Ok(false);
+                }
+                unlink(&post, [posts, by_time, by_author, by_root, by_channel, reply_counts])?;
+                for key in &edit_keys {
+                    edits.remove(key)?;
+                }
+                for key in &reaction_keys {
+                    reactions.remove(key)?;
+                }
+                Ok(true)
+            })
+            .map_err(storage_error)?;
+        if !removed {
+            return Ok(None);
+        }
+        self.post_count.fetch_sub(1, Ordering::Relaxed);
+        self.sweep_children(id)?;
+        Ok(Some(post))
+    }
+
+    fn get(&self, id: &str) -> StorageResult<Option<Post>> {
+        match self.posts.get(id.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn latest(&self, limit: usize) -> StorageResult<Vec<Post>> {
+        self.load_ids(self.by_time.iter().rev(), limit)
+    }
+
+    fn after(&self, timestamp: i64, after_id: Option<&str>, limit: usize) -> StorageResult<Vec<Post>> {
+        let start = time_key(timestamp, after_id.unwrap_or(""));
+        let entries = self
+            .by_time
+            .range(start.clone()..)
+            .filter(|entry| !matches!(entry, Ok((key, _)) if after_id.is_some() && key.as_ref() == start.as_slice()));
+        self.load_ids(entries, limit)
+    }
+
+    fn by_author(&self, author: &str, limit: usize) -> StorageResult<Vec<Post>> {
+        self.load_ids(self.by_author.scan_prefix(author_prefix(author)).rev(), limit)
+    }
+
+    fn by_channel(&self, channel: &str, limit: usize) -> StorageResult<Vec<Post>> {
+        self.load_ids(self.by_channel.scan_prefix(author_prefix(channel)).rev(), limit)
+    }
+
+    fn page(
+        &self,
+        author: Option<&str>,
+        before: Option<&Cursor>,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> StorageResult<Vec<Post>> {
+        let (tree, prefix) = match author {
+            Some(author) => (&self.by_author, author_prefix(author)),
+            None => (&self.by_time, Vec::new()),
+        };
+        let key = |cursor: &Cursor| {
+            let mut key = prefix.clone();
+            key.extend_from_slice(&time_key(cursor.timestamp, &cursor.id));
+            key
+        };
+        let lower = match after {
+            Some(cursor) => Bound::Excluded(key(cursor)),
+            None => Bound::Included(prefix.clone()),
+        };
+        let upper = match (before, author) {
+            (Some(cursor), _) => Bound::Excluded(key(cursor)),
+            // Fin du préfixe `<auteur> ++ 0x00`
+            (None, Some(author)) => {
+                let mut end = author.as_bytes().to_vec();
+                end.push(1);
+                Bound::Excluded(end)
+            }
+            (None, None) => Bound::Unbounded,
+        };
+        let entries = tree.range::<Vec<u8>, _>((lower, upper));
+        if before.is_none() && after.is_some() {
+            let mut posts = self.load_ids(entries, limit)?;
+            posts.reverse();
+            Ok(posts)
+        } else {
+            self.load_ids(entries.rev(), limit)
+        }
+    }
+
+    fn thread(&self, root: &str, limit: usize) -> StorageResult<Vec<Post>> {
+        self.load_ids(self.by_root.scan_prefix(author_prefix(root)), limit)
+    }
+
+    fn reply_count(&self, id: &str) -> StorageResult<u64> {
+        Ok(decode_count(self.reply_counts.get(id.as_bytes())?))
+    }
+
+    fn set_reaction(&self, reaction: &Reaction) -> StorageResult<bool> {
+        let key = prefixed_key(&reaction.post_id, reaction.author.as_bytes());
+        let json = serde_json::to_vec(reaction)?;
+        // Même transaction que le retrait du post : pas de réaction orpheline
+        (&self.posts, &self.reactions)
+            .transaction(|(posts, reactions)| {
+                if posts.get(reaction.post_id.as_bytes())?.is_none() {
+                    return Ok(false);
+                }
+                if let Some(bytes) = reactions.get(key.as_slice())? {
+                    let existing: Reaction =
+                        serde_json::from_slice(&bytes).map_err(|_| ConflictableTransactionError::Abort(()))?;
+                    // Départage déterministe à timestamp égal, pour que tous les relais convergent
+                    if (existing.timestamp, &existing.reaction) >= (reaction.timestamp, &reaction.reaction) {
+                        return Ok(false);
+                    }
+                }
+                reactions.insert(key.as_slice(), json.as_slice())?;
+                Ok::<_, ConflictableTransactionError<()>>(true)
+            })
+            .map_err(storage_error)
+    }
+
+    fn reaction_counts(&self, post_id: &str) -> StorageResult<BTreeMap<String, u64>> {
+        let mut counts = BTreeMap::new();
+        for entry in self.reactions.scan_prefix(author_prefix(post_id)) {
+            let (_, bytes) = entry?;
+            let reaction: Reaction = serde_json::from_slice(&bytes)?;
+            *counts.entry(reaction.reaction).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    fn add_edit(&self, edit: &Edit) -> StorageResult<bool> {
+        let key = prefixed_key(&edit.post_id, &time_key(edit.timestamp, &edit.signature));
+        let json = serde_json::to_vec(edit)?;
+        (&self.posts, &self.edits)
+            .transaction(|(posts, edits)| {
+                if posts.get(edit.post_id.as_bytes())?.is_none() || edits.get(key.as_slice())?.is_some() {
+                    return Ok(false);
+                }
+                edits.insert(key.as_slice(), json.as_slice())?;
+                Ok::<_, ConflictableTransactionError<()>>(true)
+            })
+            .map_err(storage_error)
+    }
+
+    fn edits(&self, post_id: &str) -> StorageResult<Vec<Edit>> {
+        self.edits
+            .scan_prefix(author_prefix(post_id))
+            .map(|entry| Ok(serde_json::from_slice(&entry?.1)?))
+            .collect()
+    }
+
+    fn delete(&self, delete: &Delete) -> StorageResult<Option<Post>> {
+        let key = prefixed_key(&delete.post_id, delete.author.as_bytes());
+        let json = serde_json::to_vec(delete)?;
+        let mut time_index_key = time_key(delete.timestamp, &delete.post_id);
+        time_index_key.push(0);
+        time_index_key.extend_from_slice(delete.author.as_bytes());
+        let (edit_keys, reaction_keys) = self.child_keys(&delete.post_id)?;
+
+        // Même transaction que `insert`, qui consulte les tombstones : un post
+        // ne peut pas être inséré entre le tombstone et son retrait
+        let trees = (
+            &self.posts,
+            &self.by_time,
+            &self.by_author,
+            &self.by_root,
+            &self.by_channel,
+            &self.reply_counts,
+            &self.tombstones,
+            &self.tombstones_by_time,
+            &self.edits,
+            &self.reactions,
+        );
+        let (new_tombstone, removed) = trees
+            .transaction(|(posts, by_time, by_author, by_root, by_channel, reply_counts, tombstones, tombstones_by_time, edits, reactions)| {
+                let new_tombstone = tombstones.insert(key.as_slice(), json.as_slice())?.is_none();
+                if new_tombstone {
+                    tombstones_by_time.insert(time_index_key.as_slice(), key.as_slice())?;
+                }
+                let post: Post = match posts.get(delete.post_id.as_bytes())? {
+                    Some(bytes) => serde_json::from_slice(&bytes).map_err(|_| ConflictableTransactionError::Abort(()))?,
+                    None => return Ok((new_tombstone, None)),
+                };
+                if post.author != delete.author {
+                    return Ok((new_tombstone, None));
+                }
+                unlink(&post, [posts, by_time, by_author, by_root, by_channel, reply_counts])?;
+                for key in &edit_keys {
+                    edits.remove(key)?;
+                }
+                for key in &reaction_keys {
+                    reactions.remove(key)?;
+                }
+                Ok((new_tombstone, Some(post)))
+            })
+            .map_err(storage_error)?;
+
+        if removed.is_some() {
+            self.post_count.fetch_sub(1, Ordering::Relaxed);
+            self.sweep_children(&delete.post_id)?;
+        }
+        if new_tombstone {
+            self.tombstone_count.fetch_add(1, Ordering::Relaxed);
+            self.trim_tombstones()?;
+        }
+        Ok(removed)
+    }
+
+    fn tombstone(&self, post_id: &str, author: &str) -> StorageResult<Option<Delete>> {
+        match self.tombstones.get(prefixed_key(post_id, author.as_bytes()))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn tombstones_between(&self, from: i64, until: Option<i64>, limit: usize) -> StorageResult<Vec<Delete>> {
+        let lower = Bound::Included(time_key(from, ""));
+        let upper = match until {
+            Some(until) => Bound::Excluded(time_key(until, "")),
+            None => Bound::Unbounded,
+        };
+        let mut result = Vec::new();
+        for entry in self.tombstones_by_time.range::<Vec<u8>, _>((lower, upper)).take(limit) {
+            let (_, key) = entry?;
+            // Oubliée entre-temps par `trim_tombstones`
+            if let Some(bytes) = self.tombstones.get(&key)? {
+                result.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        Ok(result)
+    }
+
+    fn evict_oldest(&self) -> StorageResult<Vec<Post>> {
+        let mut evicted = Vec::new();
+        while self.len() > self.max_posts {
+            let Some((_, id)) = self.by_time.first()? else {
+                break;
+            };
+            match self.remove(&String::from_utf8_lossy(&id))? {
+                Some(post) => evicted.push(post),
+                // Retiré entre-temps : l'appel concurrent poursuit l'éviction
+                None => break,
+            }
+        }
+        Ok(evicted)
+    }
+
+    fn set_profile(&self, profile: &Profile) -> StorageResult<bool> {
+        let key = profile.author.as_bytes();
+        let json = serde_json::to_vec(profile)?;
+        loop {
+            let current = self.profiles.get(key)?;
+            if let Some(bytes) = &current {
+                let existing: Profile = serde_json::from_slice(bytes)?;
+                // Même départage que les réactions, sur la signature
+                if (existing.timestamp, &existing.signature) >= (profile.timestamp, &profile.signature) {
+                    return Ok(false);
+                }
+            }
+            if self.profiles.compare_and_swap(key, current, Some(json.as_slice()))?.is_ok() {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn profile(&self, author: &str) -> StorageResult<Option<Profile>> {
+        match self.profiles.get(author.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn set_follow(&self, follow: &Follow, following: bool) -> StorageResult<bool> {
+        let key = prefixed_key(&follow.author, follow.target.as_bytes());
+        let json = serde_json::to_vec(&FollowEntry {
+            following,
+            follow: follow.clone(),
+        })?;
+        loop {
+            let current = self.follows.get(&key)?;
+            if let Some(bytes) = &current {
+                let existing: FollowEntry = serde_json::from_slice(bytes)?;
+                // À timestamp égal le désabonnement l'emporte, pour que tous les relais convergent
+                if (existing.follow.timestamp, !existing.following) >= (follow.timestamp, !following) {
+                    return Ok(false);
+                }
+            }
+            if self.follows.compare_and_swap(&key, current, Some(json.as_slice()))?.is_ok() {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn following(&self, author: &str) -> StorageResult<Vec<String>> {
+        let mut targets = Vec::new();
+        for entry in self.follows.scan_prefix(author_prefix(author)) {
+            let entry: FollowEntry = serde_json::from_slice(&entry?.1)?;
+            if entry.following {
+                targets.push(entry.follow.target);
+            }
+        }
+        Ok(targets)
+    }
+
+    fn insert_dm(&self, dm: &DirectMessage) -> StorageResult<bool> {
+        let json = serde_json::to_vec(dm)?;
+        let time_key = time_key(dm.timestamp, &dm.id);
+        let sent_key = prefixed_key(&dm.sender, &prefixed_key(&dm.recipient, &time_key));
+        let received_key = prefixed_key(&dm.recipient, &prefixed_key(&dm.sender, &time_key));
+        let inbox_key = prefixed_key(&dm.recipient, &time_key);
+
+        let trees = (&self.dms, &self.dms_by_conversation, &self.dms_inbox);
+        let inserted = trees
+            .transaction(|(dms, by_conversation, inbox)| {
+                if dms.get(dm.id.as_bytes())?.is_some() {
+                    return Ok(false);
+                }
+                dms.insert(dm.id.as_bytes(), json.as_slice())?;
+                by_conversation.insert(sent_key.as_slice(), dm.id.as_bytes())?;
+                by_conversation.insert(received_key.as_slice(), dm.id.as_bytes())?;
+                inbox.insert(inbox_key.as_slice(), dm.id.as_bytes())?;
+                Ok::<_, ConflictableTransactionError<()>>(true)
+            })
+            .map_err(storage_error)?;
+        Ok(inserted)
+    }
+
+    fn pending_dms(&self, recipient: &str, limit: usize) -> StorageResult<Vec<DirectMessage>> {
+        let mut result = Vec::new();
+        for entry in self.dms_inbox.scan_prefix(author_prefix(recipient)).take(limit) {
+            let (_, id) = entry?;
+            if let Some(bytes) = self.dms.get(&id)? {
+                result.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        Ok(result)
+    }
+
+    fn mark_delivered(&self, dm: &DirectMessage) -> StorageResult<()> {
+        self.dms_inbox.remove(prefixed_key(&dm.recipient, &time_key(dm.timestamp, &dm.id)))?;
+        Ok(())
+    }
+
+    fn conversations(&self, author: &str) -> StorageResult<Vec<Conversation>> {
+        let prefix = author_prefix(author);
+        let mut conversations: Vec<Conversation> = Vec::new();
+        for entry in self.dms_by_conversation.scan_prefix(&prefix) {
+            let (key, id) = entry?;
+            let rest = &key[prefix.len()..];
+            let Some(split) = rest.iter().position(|&b| b == 0) else {
+                continue;
+            };
+            let peer = String::from_utf8_lossy(&rest[..split]).into_owned();
+            let Some(bytes) = self.dms.get(&id)? else {
+                continue;
+            };
+            let dm: DirectMessage = serde_json::from_slice(&bytes)?;
+            // Les clés sont triées par interlocuteur puis par date
+            match conversations.last_mut() {
+                Some(last) if last.peer == peer => {
+                    last.messages += 1;
+                    last.last_timestamp = dm.timestamp;
+                }
+                _ => conversations.push(Conversation {
+                    peer,
+                    messages: 1,
+                    last_timestamp: dm.timestamp,
+                }),
+            }
+        }
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.last_timestamp));
+        Ok(conversations)
+    }
+
+    fn conversation(&self, author: &str, peer: &str, limit: usize) -> StorageResult<Vec<DirectMessage>> {
+        let prefix = prefixed_key(author, &author_prefix(peer));
+        let mut result = Vec::new();
+        for entry in self.dms_by_conversation.scan_prefix(prefix).rev().take(limit) {
+            let (_, id) = entry?;
+            if let Some(bytes) = self.dms.get(&id)? {
+                result.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        result.reverse();
+        Ok(result)
+    }
+
+    fn len(&self) -> usize {
+        self.post_count.load(Ordering::Relaxed)
+    }
+
+    fn size_on_disk(&self) -> StorageResult<u64> {
+        Ok(self.db.size_on_disk()?)
+    }
+
+    fn probe(&self) -> StorageResult<()> {
+        self.db.insert("health_probe", &chrono::Utc::now().timestamp().to_be_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
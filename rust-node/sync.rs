@@ -0,0 +1,156 @@
+//! Synchronisation de l'historique entre relais
+//!
+//! Gossipsub ne transporte que le trafic en direct : un relais qui rejoint le
+//! réseau (ou redémarre) demande à chaque nouveau peer les posts publiés
+//! depuis son post le plus récent, page par page. Le curseur renvoyé par le
+//! peer doit avancer à chaque page, et le nombre de pages par synchronisation
+//! est plafonné : un peer ne peut pas nous faire boucler indéfiniment.
+//!
+//! Chaque page porte aussi les suppressions datées de la même fenêtre de
+//! temps que ses posts (jusqu'à la fin pour la dernière page), pour qu'un post
+//! supprimé pendant notre absence ne revienne pas par un autre peer. Les
+//! modifications, réactions, profils, abonnements et messages privés manqués
+//! ne sont pas synchronisés : seul le trafic Gossipsub suivant les apporte.
+use crate::codec::{JsonCodec, Protocol};
+use crate::storage::PostStore;
+use crate::{Delete, Post};
+use libp2p::request_response::{self, ProtocolSupport};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+pub const SYNC_PROTOCOL: &str = "/zeta/sync/1.0.0";
+/// Nombre maximum de posts par page
+pub const PAGE_SIZE: usize = 100;
+/// Marge (en secondes) pour tolérer les horloges décalées entre auteurs
+pub const SYNC_OVERLAP_SECS: i64 = 3600;
+/// Nombre maximum de pages demandées à un peer par synchronisation
+pub const MAX_PAGES: usize = 100;
+/// Nombre maximum de suppressions par page ; au-delà, les plus récentes de la fenêtre sont perdues
+pub const MAX_TOMBSTONES_PER_PAGE: usize = 4 * PAGE_SIZE;
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    /// Timestamp à partir duquel renvoyer les posts (inclus)
+    pub since: i64,
+    /// Curseur renvoyé par la page précédente
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    /// Posts triés du plus ancien au plus récent
+    pub posts: Vec<Post>,
+    /// Présent s'il reste des posts à récupérer
+    pub next_cursor: Option<String>,
+    /// Suppressions de la fenêtre de temps de la page ; absent chez les relais
+    /// qui ne les synchronisent pas
+    #[serde(default)]
+    pub tombstones: Vec<Delete>,
+}
+
+pub type SyncCodec = JsonCodec<SyncRequest, SyncResponse>;
+pub type Behaviour = request_response::Behaviour<SyncCodec>;
+pub type Event = request_response::Event<SyncRequest, SyncResponse>;
+
+pub fn behaviour() -> Behaviour {
+    let mut config = request_response::Config::default();
+    config.set_request_timeout(Duration::from_secs(30));
+    request_response::Behaviour::new(
+        SyncCodec::new(MAX_MESSAGE_SIZE),
+        [(Protocol(SYNC_PROTOCOL), ProtocolSupport::Full)],
+        config,
+    )
+}
+
+/// Première requête envoyée à un peer : tout ce qui est plus récent que notre
+/// dernier post, moins une marge de sécurité
+pub fn initial_request(store: &dyn PostStore) -> SyncRequest {
+    let since = store
+        .latest(1)
+        .ok()
+        .and_then(|posts| posts.first().map(|p| p.timestamp - SYNC_OVERLAP_SECS))
+        .unwrap_or(0);
+    SyncRequest {
+        since,
+        cursor: None,
+        limit: PAGE_SIZE,
+    }
+}
+
+/// Raison de l'arrêt d'une synchronisation avant la dernière page
+#[derive(Debug)]
+pub enum Stop {
+    /// Curseur illisible, ou qui ne dépasse pas le précédent
+    NoProgress(String),
+    PageLimit,
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stop::NoProgress(cursor) => write!(f, "le curseur {} n'avance pas", cursor),
+            Stop::PageLimit => write!(f, "plafond de {} pages atteint", MAX_PAGES),
+        }
+    }
+}
+
+/// Synchronisation en cours avec un peer
+pub struct Progress {
+    /// Position atteinte : `since` de la première requête, puis le dernier curseur
+    last: (i64, String),
+    pages: usize,
+}
+
+impl Progress {
+    pub fn new(request: &SyncRequest) -> Self {
+        Self {
+            last: (request.since, String::new()),
+            pages: 1,
+        }
+    }
+
+    /// Requête pour la page suivante, si le curseur dépasse strictement le
+    /// précédent et que le plafond de pages n'est pas atteint
+    pub fn next_request(&mut self, cursor: String) -> Result<SyncRequest, Stop> {
+        let position = match parse_cursor(&cursor) {
+            Some((timestamp, id)) if (timestamp, id) > (self.last.0, self.last.1.as_str()) => (timestamp, id.to_string()),
+            _ => return Err(Stop::NoProgress(cursor)),
+        };
+        if self.pages >= MAX_PAGES {
+            return Err(Stop::PageLimit);
+        }
+        self.last = position;
+        self.pages += 1;
+        Ok(SyncRequest {
+            since: 0,
+            cursor: Some(cursor),
+            limit: PAGE_SIZE,
+        })
+    }
+}
+
+/// Construit la page de réponse à une requête entrante
+pub fn answer(store: &dyn PostStore, request: &SyncRequest) -> SyncResponse {
+    let limit = request.limit.clamp(1, PAGE_SIZE);
+    let (since, after_id) = match request.cursor.as_deref().and_then(parse_cursor) {
+        Some((timestamp, id)) => (timestamp, Some(id)),
+        None => (request.since, None),
+    };
+    let posts = store.after(since, after_id, limit).unwrap_or_default();
+    let last = if posts.len() == limit { posts.last() } else { None };
+    let next_cursor = last.map(|p| format!("{}:{}", p.timestamp, p.id));
+    // La page suivante reprend au timestamp du dernier post : les fenêtres ne se chevauchent pas
+    let tombstones = store
+        .tombstones_between(since, last.map(|p| p.timestamp), MAX_TOMBSTONES_PER_PAGE)
+        .unwrap_or_default();
+    SyncResponse { posts, next_cursor, tombstones }
+}
+
+/// Curseur au format `timestamp:id`
+fn parse_cursor(cursor: &str) -> Option<(i64, &str)> {
+    let (timestamp, id) = cursor.split_once(':')?;
+    Some((timestamp.parse().ok()?, id))
+}
@@ -0,0 +1,99 @@
+//! Fils de discussion
+//!
+//! Chaque réponse porte `reply_to` (son parent) et `root` (le premier post du
+//! fil). Les posts arrivent dans n'importe quel ordre : une réponse dont le
+//! parent n'a pas encore été reçu est rattachée à la racine et marquée
+//! `parent_missing`, puis retrouve sa place dès que le parent arrive.
+use crate::storage::Cursor;
+use crate::Post;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Nombre maximum de réponses chargées pour un fil
+pub const MAX_THREAD_POSTS: usize = 1000;
+
+/// Post tel que servi dans les timelines, avec son nombre de réponses directes
+/// et ses réactions
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelinePost {
+    #[serde(flatten)]
+    pub post: Post,
+    pub reply_count: u64,
+    /// Nombre de réactions par emoji, une par auteur
+    pub reactions: BTreeMap<String, u64>,
+    /// Date de la dernière modification ; `content` en est alors le contenu,
+    /// l'original signé reste disponible via `/api/posts/{id}/history`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<i64>,
+    /// Avatar du profil de l'auteur ; `author_name` est alors son nom de profil
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_avatar: Option<String>,
+}
+
+/// Page de timeline servie par `/api/posts`, le message `init` et `load_more`
+#[derive(Debug, Serialize)]
+pub struct TimelinePage {
+    /// Du plus récent au plus ancien
+    pub posts: Vec<TimelinePost>,
+    /// À passer en `before` pour la page précédente ; absent s'il n'y en a plus
+    pub before: Option<Cursor>,
+    /// À passer en `after` pour les posts plus récents
+    pub after: Option<Cursor>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadNode {
+    #[serde(flatten)]
+    pub post: TimelinePost,
+    /// Parent pas encore reçu : la réponse est affichée sous la racine
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub parent_missing: bool,
+    /// Réponses directes, de la plus ancienne à la plus récente
+    pub replies: Vec<ThreadNode>,
+}
+
+/// Construit l'arbre des réponses de `head` à partir des posts de son fil
+/// (`replies`, triés du plus ancien au plus récent)
+pub fn build(head: TimelinePost, replies: Vec<TimelinePost>) -> ThreadNode {
+    let known: HashSet<String> = replies
+        .iter()
+        .map(|r| r.post.id.clone())
+        .chain(std::iter::once(head.post.id.clone()))
+        .collect();
+    // Seule la racine du fil recueille les réponses orphelines
+    let is_root = head.post.root.is_none();
+
+    let mut children: HashMap<String, Vec<TimelinePost>> = HashMap::new();
+    let mut orphans = Vec::new();
+    for reply in replies {
+        match reply.post.reply_to.clone() {
+            Some(parent) if known.contains(&parent) => children.entry(parent).or_default().push(reply),
+            _ if is_root => orphans.push(reply),
+            _ => {}
+        }
+    }
+
+    let mut node = attach(head, &mut children);
+    node.replies.extend(orphans.into_iter().map(|orphan| ThreadNode {
+        parent_missing: true,
+        ..attach(orphan, &mut children)
+    }));
+    node.replies.sort_by_key(|n| n.post.post.timestamp);
+    node
+}
+
+/// Rattache récursivement les réponses ; chaque liste n'est prise qu'une fois,
+/// ce qui protège des cycles `reply_to` forgés
+fn attach(post: TimelinePost, children: &mut HashMap<String, Vec<TimelinePost>>) -> ThreadNode {
+    let replies = children
+        .remove(&post.post.id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| attach(child, children))
+        .collect();
+    ThreadNode {
+        post,
+        parent_missing: false,
+        replies,
+    }
+}
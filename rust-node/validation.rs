@@ -0,0 +1,294 @@
+//! Validation applicative des posts, réactions, modifications, suppressions,
+//! profils, abonnements et messages privés
+//!
+//! Appliquée à l'entrée (REST, WebSocket, sync) et avant de relayer un message
+//! Gossipsub : un post invalide s'arrête au premier relais honnête.
+use crate::channels;
+use crate::media;
+use crate::moderation::Verdict;
+use crate::protocol::DecodeError;
+use crate::rate_limit::Scope;
+use crate::{signing, Delete, DirectMessage, Edit, Follow, Post, Profile, Reaction};
+use libp2p::gossipsub::MessageAcceptance;
+use std::fmt;
+
+/// Longueur maximale du contenu, en caractères (même limite que l'interface)
+pub const MAX_CONTENT_CHARS: usize = 280;
+pub const MAX_AUTHOR_NAME_CHARS: usize = 64;
+/// Un emoji composé (drapeau, famille...) tient en quelques caractères
+pub const MAX_REACTION_CHARS: usize = 16;
+pub const MAX_BIO_CHARS: usize = 280;
+pub const MAX_AVATAR_CHARS: usize = 512;
+/// Une clé publique Ed25519 ou X25519 en hex
+const AUTHOR_KEY_CHARS: usize = 64;
+/// Identifiants de posts et de messages privés : `[A-Za-z0-9-]{1,64}` (un UUID
+/// y tient). Ils servent de préfixes de clés sled, séparés par 0x00 : un
+/// identifiant arbitraire pourrait déborder sur les entrées d'un autre
+const MAX_ID_CHARS: usize = 64;
+/// Nonce AES-GCM de 12 octets, en hex
+const DM_NONCE_CHARS: usize = 24;
+/// Chiffré en hex : environ 4 Ko de texte clair
+pub const MAX_DM_CIPHERTEXT_CHARS: usize = 8192;
+/// Avance tolérée sur l'horloge locale pour le timestamp d'un post
+pub const MAX_CLOCK_SKEW_SECS: i64 = 300;
+/// Taille maximale d'un message Gossipsub
+pub const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+
+#[derive(Debug)]
+pub enum Rejection {
+    Oversized,
+    EmptyContent,
+    BadTimestamp,
+    BadSignature,
+    BadThread,
+    /// Nom de canal invalide, ou post reçu sur le topic d'un autre canal
+    BadChannel,
+    /// Clé, nonce ou chiffré d'un message privé mal encodé
+    BadEncoding,
+    /// Identifiant de post ou de message privé hors de `[A-Za-z0-9-]{1,64}`
+    BadId,
+    /// Pièces jointes trop nombreuses, hash ou type invalide, texte trop long
+    BadAttachment,
+    /// Modification ou suppression signée par une autre clé que celle de l'auteur
+    NotAuthor,
+    /// Post supprimé par son auteur
+    Deleted,
+    /// Réaction ou modification d'un post que ce relais ne stocke pas
+    UnknownPost,
+    Undecodable(DecodeError),
+    RateLimited(Scope),
+    Moderated(Verdict),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Oversized => write!(f, "contenu trop long"),
+            Rejection::EmptyContent => write!(f, "contenu vide"),
+            Rejection::BadTimestamp => write!(f, "timestamp invalide"),
+            Rejection::BadSignature => write!(f, "signature invalide"),
+            Rejection::BadThread => write!(f, "reply_to et root incohérents"),
+            Rejection::BadChannel => write!(f, "canal invalide pour ce topic"),
+            Rejection::BadEncoding => write!(f, "clé, nonce ou chiffré mal encodé"),
+            Rejection::BadId => write!(f, "identifiant invalide"),
+            Rejection::BadAttachment => write!(f, "pièce jointe invalide"),
+            Rejection::NotAuthor => write!(f, "seul l'auteur du post peut le modifier ou le supprimer"),
+            Rejection::Deleted => write!(f, "post supprimé par son auteur"),
+            Rejection::UnknownPost => write!(f, "post inconnu de ce relais"),
+            Rejection::Undecodable(e) => write!(f, "{}", e),
+            Rejection::RateLimited(scope) => write!(f, "limite de débit atteinte ({})", scope.as_str()),
+            Rejection::Moderated(verdict) => write!(f, "refusé par la modération: {}", verdict),
+        }
+    }
+}
+
+impl Rejection {
+    /// Motif stable, pour les labels de métriques
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Rejection::Oversized => "oversized",
+            Rejection::EmptyContent => "empty_content",
+            Rejection::BadTimestamp => "bad_timestamp",
+            Rejection::BadSignature => "bad_signature",
+            Rejection::BadThread => "bad_thread",
+            Rejection::BadChannel => "bad_channel",
+            Rejection::BadEncoding => "bad_encoding",
+            Rejection::BadId => "bad_id",
+            Rejection::BadAttachment => "bad_attachment",
+            Rejection::NotAuthor => "not_author",
+            Rejection::Deleted => "deleted",
+            Rejection::UnknownPost => "unknown_post",
+            Rejection::Undecodable(_) => "undecodable",
+            Rejection::RateLimited(_) => "rate_limited",
+            Rejection::Moderated(_) => "moderated",
+        }
+    }
+
+    /// Verdict Gossipsub : les messages d'une version ou d'un kind inconnus,
+    /// ceux au-delà des limites de débit, ceux refusés par la modération
+    /// locale, les copies tardives d'un post supprimé et les réactions ou
+    /// modifications d'un post inconnu (arrivées avant lui, ou après son
+    /// retrait) sont ignorés sans pénaliser le peer qui les relaie ; tout le
+    /// reste est rejeté
+    pub fn acceptance(&self) -> MessageAcceptance {
+        match self {
+            Rejection::Undecodable(DecodeError::UnsupportedVersion(_) | DecodeError::UnknownKind(_))
+            | Rejection::RateLimited(_)
+            | Rejection::Moderated(_)
+            | Rejection::Deleted
+            | Rejection::UnknownPost => MessageAcceptance::Ignore,
+            _ => MessageAcceptance::Reject,
+        }
+    }
+}
+
+pub fn validate_post(post: &Post) -> Result<(), Rejection> {
+    let ids = [Some(&post.id), post.reply_to.as_ref(), post.root.as_ref()];
+    if !ids.into_iter().flatten().all(|id| is_valid_id(id)) {
+        return Err(Rejection::BadId);
+    }
+    // Un post peut se limiter à ses pièces jointes
+    if post.content.trim().is_empty() && post.attachments.is_empty() {
+        return Err(Rejection::EmptyContent);
+    }
+    if post.content.chars().count() > MAX_CONTENT_CHARS
+        || post.author_name.chars().count() > MAX_AUTHOR_NAME_CHARS
+    {
+        return Err(Rejection::Oversized);
+    }
+    validate_timestamp(post.timestamp)?;
+    // Une réponse indique son parent et la racine du fil, un post simple aucun des deux
+    match (&post.reply_to, &post.root) {
+        (None, None) => {}
+        (Some(parent), Some(root)) if *parent != post.id && *root != post.id => {}
+        _ => return Err(Rejection::BadThread),
+    }
+    if post.channel.as_deref().is_some_and(|c| !channels::is_valid_name(c)) {
+        return Err(Rejection::BadChannel);
+    }
+    validate_attachments(&post.attachments)?;
+    if !signing::verify_post(post) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+fn validate_attachments(attachments: &[media::Attachment]) -> Result<(), Rejection> {
+    if attachments.len() > media::MAX_ATTACHMENTS {
+        return Err(Rejection::BadAttachment);
+    }
+    let valid = |a: &media::Attachment| {
+        media::is_valid_hash(&a.hash)
+            && media::KNOWN_TYPES.contains(&a.mime.as_str())
+            && a.size > 0
+            && a.size <= media::MAX_MEDIA_BYTES as u64
+            && a.name.as_ref().is_none_or(|n| n.chars().count() <= media::MAX_NAME_CHARS)
+            && a.alt.as_ref().is_none_or(|alt| alt.chars().count() <= media::MAX_ALT_CHARS)
+    };
+    if attachments.iter().all(valid) {
+        Ok(())
+    } else {
+        Err(Rejection::BadAttachment)
+    }
+}
+
+pub fn validate_reaction(reaction: &Reaction) -> Result<(), Rejection> {
+    if !is_valid_id(&reaction.post_id) {
+        return Err(Rejection::BadId);
+    }
+    if reaction.reaction.trim().is_empty() {
+        return Err(Rejection::EmptyContent);
+    }
+    if reaction.reaction.chars().count() > MAX_REACTION_CHARS {
+        return Err(Rejection::Oversized);
+    }
+    validate_timestamp(reaction.timestamp)?;
+    if !signing::verify_reaction(reaction) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+pub fn validate_edit(edit: &Edit) -> Result<(), Rejection> {
+    if !is_valid_id(&edit.post_id) {
+        return Err(Rejection::BadId);
+    }
+    if edit.content.trim().is_empty() {
+        return Err(Rejection::EmptyContent);
+    }
+    if edit.content.chars().count() > MAX_CONTENT_CHARS {
+        return Err(Rejection::Oversized);
+    }
+    validate_timestamp(edit.timestamp)?;
+    if !signing::verify_edit(edit) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+pub fn validate_delete(delete: &Delete) -> Result<(), Rejection> {
+    if !is_valid_id(&delete.post_id) {
+        return Err(Rejection::BadId);
+    }
+    validate_timestamp(delete.timestamp)?;
+    if !signing::verify_delete(delete) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+pub fn validate_profile(profile: &Profile) -> Result<(), Rejection> {
+    if profile.display_name.trim().is_empty() {
+        return Err(Rejection::EmptyContent);
+    }
+    if profile.display_name.chars().count() > MAX_AUTHOR_NAME_CHARS
+        || profile.bio.chars().count() > MAX_BIO_CHARS
+        || profile.avatar.as_ref().is_some_and(|a| a.chars().count() > MAX_AVATAR_CHARS)
+    {
+        return Err(Rejection::Oversized);
+    }
+    if profile.dm_key.as_deref().is_some_and(|key| !is_hex(key, AUTHOR_KEY_CHARS)) {
+        return Err(Rejection::BadEncoding);
+    }
+    validate_timestamp(profile.timestamp)?;
+    if !signing::verify_profile(profile) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+/// `following` distingue `Follow` de `Unfollow`, signés sous des domaines différents
+pub fn validate_follow(follow: &Follow, following: bool) -> Result<(), Rejection> {
+    if !is_hex(&follow.target, AUTHOR_KEY_CHARS) {
+        return Err(Rejection::BadEncoding);
+    }
+    validate_timestamp(follow.timestamp)?;
+    if !signing::verify_follow(follow, following) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+/// Les relais ne peuvent pas lire le contenu : seuls l'encodage, la taille
+/// et la signature de l'expéditeur sont vérifiés
+pub fn validate_direct_message(dm: &DirectMessage) -> Result<(), Rejection> {
+    if !is_valid_id(&dm.id) {
+        return Err(Rejection::BadId);
+    }
+    if dm.ciphertext.is_empty() {
+        return Err(Rejection::EmptyContent);
+    }
+    if dm.ciphertext.len() > MAX_DM_CIPHERTEXT_CHARS {
+        return Err(Rejection::Oversized);
+    }
+    let well_formed = [&dm.sender, &dm.recipient, &dm.sender_dm_key, &dm.recipient_dm_key]
+        .iter()
+        .all(|key| is_hex(key, AUTHOR_KEY_CHARS))
+        && is_hex(&dm.nonce, DM_NONCE_CHARS)
+        && dm.ciphertext.len().is_multiple_of(2)
+        && dm.ciphertext.bytes().all(|b| b.is_ascii_hexdigit());
+    if !well_formed {
+        return Err(Rejection::BadEncoding);
+    }
+    validate_timestamp(dm.timestamp)?;
+    if !signing::verify_direct_message(dm) {
+        return Err(Rejection::BadSignature);
+    }
+    Ok(())
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_ID_CHARS && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn validate_timestamp(timestamp: i64) -> Result<(), Rejection> {
+    let now = chrono::Utc::now().timestamp();
+    if timestamp <= 0 || timestamp > now + MAX_CLOCK_SKEW_SECS {
+        return Err(Rejection::BadTimestamp);
+    }
+    Ok(())
+}
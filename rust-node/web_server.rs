@@ -1,45 +1,173 @@
 //! Serveur web avec WebSocket pour l'interface utilisateur
-use crate::{NetworkMessage, NetworkState, PeerInfo, Post};
+use crate::moderation::Rules;
+use crate::rate_limit::{Kind, RateLimits, Scope};
+use crate::media::{self, Attachment, MediaError};
+use crate::metrics::Source;
+use crate::search::{self, Query};
+use crate::storage::Cursor;
+use crate::thread::TimelinePost;
+use crate::validation::Rejection;
+use crate::{
+    signing, validation, Delete, DirectMessage, Edit, Follow, NetworkMessage, NetworkState, PeerInfo, Post, Profile,
+    Reaction,
+};
+use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::convert::Infallible;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use warp::http::StatusCode;
 use warp::ws::{Message, WebSocket};
-use warp::Filter;
+use warp::{Filter, Reply};
 
 #[derive(Serialize)]
 struct NetworkInfo {
     local_peer_id: String,
     local_name: String,
+    /// Adresses libp2p du nœud, dont `/ws` pour les navigateurs js-libp2p
+    listen_addrs: Vec<String>,
     peers: Vec<PeerInfo>,
-    posts: Vec<Post>,
+    posts: Vec<TimelinePost>,
     is_relay: bool,
+    /// Compteurs des messages Gossipsub rejetés au décodage
+    decode_failures: serde_json::Value,
+    /// Posts refusés par les limites de débit, par portée
+    rate_limited: serde_json::Value,
 }
 
+/// Corps de `/api/post` : soit un post déjà signé par le client, soit
+/// seulement `content` + `author_name`, auquel cas le nœud signe avec sa clé
 #[derive(Deserialize)]
 struct PostRequest {
     content: String,
     author_name: String,
+    id: Option<String>,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+    /// Réponse : `root` peut être omis si le nœud signe et connaît le parent
+    reply_to: Option<String>,
+    root: Option<String>,
+    /// Canal, que le relais doit avoir rejoint
+    channel: Option<String>,
+    /// Blobs déjà envoyés à ce relais par `/api/media`
+    #[serde(default)]
+    attachments: Vec<Attachment>,
 }
 
+/// Corps de `/api/reaction` : réaction signée par le client, ou signée par le nœud
+/// si `author`, `timestamp` et `signature` sont absents
 #[derive(Deserialize)]
-struct WsMessage {
-    #[serde(rename = "type")]
-    msg_type: String,
-    // Format avec data (app.html de PythonAnywhere)
-    data: Option<WsPostData>,
-    // Format direct (static/index.html du relais)
-    content: Option<String>,
-    author_name: Option<String>,
+struct ReactionRequest {
+    post_id: String,
+    reaction: String,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+}
+
+/// Corps de `/api/edit` : modification signée par le client, ou signée par le nœud
+/// (qui doit alors être l'auteur du post) si la requête porte le jeton d'administration
+#[derive(Deserialize)]
+struct EditRequest {
+    post_id: String,
+    content: String,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+}
+
+/// Corps de `/api/delete`, sur le même modèle que `/api/edit`
+#[derive(Deserialize)]
+struct DeleteRequest {
+    post_id: String,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+}
+
+/// Corps de `/api/profile` : profil signé par le client, ou profil du nœud avec le
+/// jeton d'administration
+#[derive(Deserialize)]
+struct ProfileRequest {
+    display_name: String,
+    #[serde(default)]
+    bio: String,
+    avatar: Option<String>,
+    dm_key: Option<String>,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+}
+
+/// Corps de `/api/follow` et `/api/unfollow`, sur le même modèle que `/api/profile`
+#[derive(Deserialize)]
+struct FollowRequest {
+    target: String,
+    author: Option<String>,
+    timestamp: Option<i64>,
+    signature: Option<String>,
+}
+
+/// Paramètres de `/api/posts` : curseurs `<timestamp>:<id>` renvoyés par la page précédente
+#[derive(Deserialize)]
+struct PageRequest {
+    before: Option<String>,
+    after: Option<String>,
+    limit: Option<usize>,
+    author: Option<String>,
+}
+
+/// Paramètres de `/api/search` ; `author` accepte plusieurs clés séparées par des virgules
+#[derive(Deserialize)]
+struct SearchRequest {
+    #[serde(default)]
+    q: String,
+    author: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<usize>,
 }
 
 #[derive(Deserialize)]
-struct WsPostData {
+struct WsMessage {
+    #[serde(rename = "type")]
+    msg_type: String,
+    // Réponse au challenge : {type: "auth", public_key, signature, author_name?}
+    public_key: Option<String>,
+    // Post signé par le navigateur
     id: Option<String>,
     author: Option<String>,
+    author_name: Option<String>,
     content: Option<String>,
     timestamp: Option<i64>,
+    signature: Option<String>,
+    reply_to: Option<String>,
+    root: Option<String>,
+    // Réaction signée : {type: "reaction", post_id, reaction, author, timestamp, signature}
+    // Modification : {type: "edit", post_id, content, author, timestamp, signature}
+    // Suppression : {type: "delete", post_id, author, timestamp, signature}
+    post_id: Option<String>,
+    reaction: Option<String>,
+    // Profil : {type: "profile", author, display_name, bio, avatar, dm_key, timestamp, signature}
+    display_name: Option<String>,
+    bio: Option<String>,
+    avatar: Option<String>,
+    dm_key: Option<String>,
+    // Abonnement : {type: "follow" | "unfollow", author, target, timestamp, signature}
+    target: Option<String>,
+    // Choix de la timeline : {type: "subscribe", mode: "feed" | "global"}
+    mode: Option<String>,
+    // Page précédente de la timeline globale : {type: "load_more", before}
+    before: Option<String>,
+    // Canal d'un post, ou {type: "join" | "leave", channel}
+    channel: Option<String>,
+    // Pièces jointes d'un post, envoyées avant par `/api/media`
+    attachments: Option<Vec<Attachment>>,
+    // Message privé : {type: "dm", ...DirectMessage}, relu tel quel depuis le texte
 }
 
 type SharedState = Arc<RwLock<(
@@ -56,7 +184,8 @@ pub async fn start_server(
     ws_to_p2p_tx: mpsc::UnboundedSender<NetworkMessage>,
     local_name: String,
     is_relay: bool,
-    port: u16,
+    bind: SocketAddr,
+    admin_token: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let shared_state: SharedState = Arc::new(RwLock::new((
         network_state.clone(),
@@ -66,6 +195,13 @@ pub async fn start_server(
         is_relay,
     )));
 
+    // Jeton d'administration : routes de modération, et messages signés par la clé du relais
+    let admin_token = Arc::new(admin_token);
+    let node_signer = {
+        let token = admin_token.clone();
+        warp::header::optional::<String>("authorization").and(warp::any().map(move || token.clone()))
+    };
+
     // Route API - État du réseau
     let state_for_api = shared_state.clone();
     let network_info = warp::path!("api" / "network")
@@ -78,18 +214,220 @@ pub async fn start_server(
     let post_message = warp::path!("api" / "post")
         .and(warp::post())
         .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
         .and(warp::any().map(move || state_for_post.clone()))
         .and_then(create_post);
 
+    // Route API - Réagir à un post
+    let state_for_reaction = shared_state.clone();
+    let post_reaction = warp::path!("api" / "reaction")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_reaction.clone()))
+        .and_then(create_reaction);
+
+    // Routes API - Modifier / supprimer un de ses posts
+    let state_for_edit = shared_state.clone();
+    let post_edit = warp::path!("api" / "edit")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_edit.clone()))
+        .and_then(create_edit);
+    let state_for_delete = shared_state.clone();
+    let post_delete = warp::path!("api" / "delete")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_delete.clone()))
+        .and_then(create_delete);
+
+    // Routes API - Profils
+    let state_for_profile = shared_state.clone();
+    let post_profile = warp::path!("api" / "profile")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_profile.clone()))
+        .and_then(create_profile);
+    let profile_state = network_state.clone();
+    let profile = warp::path!("api" / "profile" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || profile_state.clone()))
+        .and_then(get_profile);
+
+    // Routes API - Abonnements et fil personnel
+    let state_for_follow = shared_state.clone();
+    let post_follow = warp::path!("api" / "follow")
+        .and(warp::post())
+        .and(warp::any().map(|| true))
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_follow.clone()))
+        .and_then(create_follow);
+    let state_for_unfollow = shared_state.clone();
+    let post_unfollow = warp::path!("api" / "unfollow")
+        .and(warp::post())
+        .and(warp::any().map(|| false))
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(node_signer.clone())
+        .and(warp::any().map(move || state_for_unfollow.clone()))
+        .and_then(create_follow);
+    let following_state = network_state.clone();
+    let following = warp::path!("api" / "following" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || following_state.clone()))
+        .and_then(get_following);
+    let feed_state = network_state.clone();
+    let feed = warp::path!("api" / "feed" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || feed_state.clone()))
+        .and_then(get_feed);
+
+    // Routes API - Canaux
+    let channels_state = network_state.clone();
+    let channel_list = warp::path!("api" / "channels")
+        .and(warp::get())
+        .and(warp::any().map(move || channels_state.clone()))
+        .and_then(list_channels);
+    let channel_posts_state = network_state.clone();
+    let channel_posts = warp::path!("api" / "channels" / String / "posts")
+        .and(warp::get())
+        .and(warp::any().map(move || channel_posts_state.clone()))
+        .and_then(get_channel_posts);
+
+    // Routes médias : envoi d'un blob, puis service par son hash
+    let upload_state = network_state.clone();
+    let upload = warp::path!("api" / "media")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(media::MAX_MEDIA_BYTES as u64))
+        .and(warp::body::bytes())
+        .and(warp::addr::remote())
+        .and(warp::any().map(move || upload_state.clone()))
+        .and_then(upload_media);
+    let media_state = network_state.clone();
+    let serve = warp::path!("media" / String)
+        .and(warp::get())
+        .and(warp::addr::remote())
+        .and(warp::any().map(move || media_state.clone()))
+        .and_then(serve_media);
+
+    // Route API - Timeline paginée
+    let page_state = network_state.clone();
+    let page = warp::path!("api" / "posts")
+        .and(warp::get())
+        .and(warp::query::<PageRequest>())
+        .and(warp::any().map(move || page_state.clone()))
+        .and_then(get_posts_page);
+
+    // Route API - Recherche plein texte
+    let search_state = network_state.clone();
+    let search = warp::path!("api" / "search")
+        .and(warp::get())
+        .and(warp::query::<SearchRequest>())
+        .and(warp::any().map(move || search_state.clone()))
+        .and_then(search_posts);
+
+    // Route Prometheus
+    let metrics_state = network_state.clone();
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .and(warp::any().map(move || metrics_state.clone()))
+        .and_then(get_metrics);
+
+    // Routes de supervision : vivacité, puis participation au réseau
+    let healthz_state = network_state.clone();
+    let healthz = warp::path!("healthz")
+        .and(warp::get())
+        .and(warp::any().map(move || healthz_state.clone()))
+        .and_then(get_healthz);
+    let readyz_state = network_state.clone();
+    let readyz = warp::path!("readyz")
+        .and(warp::get())
+        .and(warp::any().map(move || readyz_state.clone()))
+        .and_then(get_readyz);
+
+    // Routes API - Messages privés
+    let state_for_dm = shared_state.clone();
+    let post_dm = warp::path!("api" / "dm")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(warp::any().map(move || state_for_dm.clone()))
+        .and_then(send_direct_message);
+    let conversations_state = network_state.clone();
+    let conversations = warp::path!("api" / "conversations" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || conversations_state.clone()))
+        .and_then(get_conversations);
+    let conversation_state = network_state.clone();
+    let conversation = warp::path!("api" / "conversations" / String / String)
+        .and(warp::get())
+        .and(warp::any().map(move || conversation_state.clone()))
+        .and_then(get_conversation);
+
+    // Route API - Historique des modifications d'un post
+    let history_state = network_state.clone();
+    let history = warp::path!("api" / "posts" / String / "history")
+        .and(warp::get())
+        .and(warp::any().map(move || history_state.clone()))
+        .and_then(get_history);
+
+    // Route API - Fil de discussion
+    let thread_state = network_state.clone();
+    let thread = warp::path!("api" / "thread" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || thread_state.clone()))
+        .and_then(get_thread);
+
+    // Routes d'administration - Modération (jeton requis)
+    let admin = {
+        let state = network_state.clone();
+        let token = admin_token.clone();
+        warp::header::optional::<String>("authorization")
+            .and(warp::any().map(move || state.clone()))
+            .and(warp::any().map(move || token.clone()))
+    };
+    let moderation_get = warp::path!("api" / "moderation")
+        .and(warp::get())
+        .and(admin.clone())
+        .and_then(get_moderation);
+    let moderation_put = warp::path!("api" / "moderation")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(admin.clone())
+        .and_then(put_moderation);
+    let remove_post = warp::path!("api" / "posts" / String)
+        .and(warp::delete())
+        .and(admin.clone())
+        .and_then(remove_post);
+    let channel_join = warp::path!("api" / "channels" / String / "join")
+        .and(warp::post())
+        .and(admin.clone())
+        .and_then(join_channel);
+    let channel_leave = warp::path!("api" / "channels" / String / "leave")
+        .and(warp::post())
+        .and(admin)
+        .and_then(leave_channel);
+
     // Route WebSocket
     let ws_state = network_state.clone();
     let ws_p2p_state = shared_state.clone();
     let websocket = warp::path("ws")
         .and(warp::ws())
+        .and(warp::addr::remote())
         .and(warp::any().map(move || ws_state.clone()))
         .and(warp::any().map(move || ws_p2p_state.clone()))
-        .map(|ws: warp::ws::Ws, state: NetworkState, p2p_state: SharedState| {
-            ws.on_upgrade(move |socket| handle_websocket(socket, state, p2p_state))
+        .map(|ws: warp::ws::Ws, remote: Option<SocketAddr>, state: NetworkState, p2p_state: SharedState| {
+            ws.on_upgrade(move |socket| handle_websocket(socket, remote, state, p2p_state))
         });
 
     // Page HTML principale intégrée
@@ -100,86 +438,943 @@ pub async fn start_server(
     // CORS
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_methods(vec!["GET", "POST", "OPTIONS"])
-        .allow_headers(vec!["Content-Type"]);
+        .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+        .allow_headers(vec!["Content-Type", "Authorization"]);
 
-    let routes = websocket
+    // Chaîne découpée en deux filtres boxés : au-delà, le type du futur
+    // dépasse la limite de récursion du compilateur
+    let social = websocket
         .or(network_info)
         .or(post_message)
+        .or(post_reaction)
+        .or(post_edit)
+        .or(post_delete)
+        .or(history)
+        .or(post_profile)
+        .or(profile)
+        .or(post_follow)
+        .or(post_unfollow)
+        .or(following)
+        .or(feed)
+        .or(channel_list)
+        .or(channel_posts)
+        .boxed();
+
+    let routes = social
+        .or(upload)
+        .or(serve)
+        .or(page)
+        .or(search)
+        .or(metrics)
+        .or(healthz)
+        .or(readyz)
+        .or(post_dm)
+        .or(conversations)
+        .or(conversation)
+        .or(thread)
+        .or(moderation_get)
+        .or(moderation_put)
+        .or(remove_post)
+        .or(channel_join)
+        .or(channel_leave)
         .or(index)
         .with(cors);
 
-    tracing::info!("🌐 Serveur web sur http://localhost:{}", port);
-    tracing::info!("🔌 WebSocket sur ws://localhost:{}/ws", port);
+    tracing::info!("🌐 Serveur web sur http://{}", bind);
+    tracing::info!("🔌 WebSocket sur ws://{}/ws", bind);
 
-    warp::serve(routes).run(([0, 0, 0, 0], port)).await;
+    warp::serve(routes).run(bind).await;
     Ok(())
 }
 
+async fn get_thread(id: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    match network_state.thread(&id) {
+        Some(thread) => Ok(warp::reply::with_status(warp::reply::json(&thread), StatusCode::OK)),
+        None => Ok(error_reply(StatusCode::NOT_FOUND, "post inconnu")),
+    }
+}
+
+async fn get_metrics(network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    // sled mesure sa taille en parcourant ses fichiers
+    let store = network_state.store.clone();
+    let storage_bytes = match tokio::task::spawn_blocking(move || store.size_on_disk()).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(e)) => {
+            tracing::warn!("⚠️ Taille du stockage inconnue: {}", e);
+            0
+        }
+        Err(_) => 0,
+    };
+    let media_bytes = network_state.media.stored_bytes();
+    let body = network_state.metrics.render(network_state.store.len(), storage_bytes, media_bytes);
+    Ok(warp::reply::with_header(body, "Content-Type", "text/plain; version=0.0.4; charset=utf-8"))
+}
+
+async fn get_healthz(network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    let body = serde_json::json!({"status": "ok", "uptime_secs": network_state.health.uptime().as_secs()});
+    Ok(warp::reply::json(&body))
+}
+
+/// 503 tant qu'un des contrôles échoue, avec le détail de chacun
+async fn get_readyz(network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    let readiness = network_state.health.readiness();
+    let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Ok(warp::reply::with_status(warp::reply::json(&readiness), status))
+}
+
 async fn get_network_info(state: SharedState) -> Result<impl warp::Reply, Infallible> {
     let state_guard = state.read().await;
     let (network_state, _, _, local_name, is_relay) = &*state_guard;
 
     let peers: Vec<PeerInfo> = network_state.peers.read().await.values().cloned().collect();
-    let posts = network_state.posts.read().await.clone();
+    let posts = network_state.timeline(crate::TIMELINE_LIMIT);
 
     let info = NetworkInfo {
         local_peer_id: network_state.local_peer_id.to_string(),
         local_name: local_name.clone(),
+        listen_addrs: network_state.listen_addrs.read().await.clone(),
         peers,
         posts,
         is_relay: *is_relay,
+        decode_failures: network_state.decode_stats.snapshot(),
+        rate_limited: network_state.rate_limits.snapshot(),
     };
 
     Ok(warp::reply::json(&info))
 }
 
-async fn create_post(post_req: PostRequest, state: SharedState) -> Result<impl warp::Reply, Infallible> {
+async fn create_post(
+    post_req: PostRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
     let state_guard = state.read().await;
     let (network_state, post_tx, _, _, _) = &*state_guard;
 
-    let post = Post {
-        id: uuid::Uuid::new_v4().to_string(),
-        author: network_state.local_peer_id.to_string(),
-        author_name: post_req.author_name,
-        content: post_req.content,
-        timestamp: chrono::Utc::now().timestamp(),
+    let post = match (post_req.id, post_req.author, post_req.timestamp, post_req.signature) {
+        (Some(id), Some(author), Some(timestamp), Some(signature)) => Post {
+            id,
+            author,
+            author_name: post_req.author_name,
+            content: post_req.content,
+            timestamp,
+            reply_to: post_req.reply_to,
+            root: post_req.root,
+            channel: post_req.channel,
+            attachments: post_req.attachments,
+            signature,
+        },
+        (None, None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            // Le nœud retrouve la racine du fil à partir du parent
+            let root = match (&post_req.reply_to, post_req.root) {
+                (Some(parent), None) => match network_state.store.get(parent) {
+                    Ok(Some(parent)) => Some(parent.root.unwrap_or(parent.id)),
+                    _ => return Ok(error_reply(StatusCode::BAD_REQUEST, "post parent inconnu, précisez root")),
+                },
+                (_, root) => root,
+            };
+            let mut post = Post {
+                id: uuid::Uuid::new_v4().to_string(),
+                author: String::new(),
+                author_name: post_req.author_name,
+                content: post_req.content,
+                timestamp: chrono::Utc::now().timestamp(),
+                reply_to: post_req.reply_to,
+                root,
+                channel: post_req.channel,
+                attachments: post_req.attachments,
+                signature: String::new(),
+            };
+            signing::sign_post(&mut post, &network_state.author_key);
+            post
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "id, author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
     };
 
+    let metrics = &network_state.metrics;
+    if let Err(e) = validation::validate_post(&post) {
+        tracing::warn!("🚫 Post REST {} rejeté: {}", post.id, e);
+        metrics.post_rejected(Source::Api, &e);
+        return Ok(error_reply(StatusCode::BAD_REQUEST, &e.to_string()));
+    }
+    if let Some(channel) = post.channel.as_deref().filter(|c| !network_state.channels.is_joined(c)) {
+        metrics.post_rejected(Source::Api, &Rejection::BadChannel);
+        return Ok(error_reply(StatusCode::BAD_REQUEST, &format!("canal {} non rejoint par ce relais", channel)));
+    }
+    if let Err(e) = network_state.check_attachments(&post).await {
+        metrics.post_rejected(Source::Api, &Rejection::BadAttachment);
+        return Ok(error_reply(StatusCode::BAD_REQUEST, &e));
+    }
+    if let Err(verdict) = network_state.moderation.check_post(&post) {
+        tracing::warn!("🛡️ Post REST {} refusé: {}", post.id, verdict);
+        let rejection = Rejection::Moderated(verdict);
+        metrics.post_rejected(Source::Api, &rejection);
+        return Ok(rejection_reply(&rejection));
+    }
+    if let Err(scope) = check_limits(&network_state.rate_limits, remote, &post.author, Kind::Post) {
+        tracing::warn!("🐢 Post REST {} refusé: limite {} atteinte", post.id, scope.as_str());
+        metrics.post_rejected(Source::Api, &Rejection::RateLimited(scope));
+        return Ok(error_reply(StatusCode::TOO_MANY_REQUESTS, &rate_limit_message(scope)));
+    }
+
+    metrics.post_received(Source::Api);
     let _ = post_tx.send(post.clone());
     tracing::info!("📝 Post créé via REST: {}", post.content);
 
-    Ok(warp::reply::json(&post))
+    Ok(warp::reply::with_status(warp::reply::json(&post), StatusCode::OK))
+}
+
+/// Vérifie l'en-tête `Authorization: Bearer <admin_token>`
+fn check_admin(authorization: Option<String>, token: &Option<String>) -> Result<(), warp::reply::WithStatus<warp::reply::Json>> {
+    let Some(token) = token else {
+        return Err(error_reply(StatusCode::FORBIDDEN, "API d'administration désactivée (admin_token non configuré)"));
+    };
+    let provided = authorization.as_deref().and_then(|h| h.strip_prefix("Bearer "));
+    match provided {
+        Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err(error_reply(StatusCode::UNAUTHORIZED, "jeton d'administration invalide")),
+    }
+}
+
+/// Signer avec la clé du relais est réservé à l'administrateur : sans jeton,
+/// n'importe quel client (ou page web, CORS étant ouvert) agirait au nom du relais
+fn check_node_signing(
+    authorization: Option<String>,
+    token: &Option<String>,
+) -> Result<(), warp::reply::WithStatus<warp::reply::Json>> {
+    check_admin(authorization, token).map_err(|_| {
+        error_reply(
+            StatusCode::UNAUTHORIZED,
+            "author, timestamp et signature requis (signature par le relais réservée à l'administrateur)",
+        )
+    })
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn get_moderation(
+    authorization: Option<String>,
+    network_state: NetworkState,
+    token: Arc<Option<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Err(reply) = check_admin(authorization, &token) {
+        return Ok(reply);
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&network_state.moderation.rules()),
+        StatusCode::OK,
+    ))
+}
+
+/// Remplace les règles (hors posts retirés, gérés par `DELETE /api/posts/{id}`)
+async fn put_moderation(
+    rules: Rules,
+    authorization: Option<String>,
+    network_state: NetworkState,
+    token: Arc<Option<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Err(reply) = check_admin(authorization, &token) {
+        return Ok(reply);
+    }
+    if let Err(e) = network_state.moderation.replace(rules) {
+        return Ok(error_reply(StatusCode::BAD_REQUEST, &e));
+    }
+    tracing::info!("🛡️ Règles de modération mises à jour");
+    Ok(warp::reply::with_status(
+        warp::reply::json(&network_state.moderation.rules()),
+        StatusCode::OK,
+    ))
+}
+
+async fn remove_post(
+    id: String,
+    authorization: Option<String>,
+    network_state: NetworkState,
+    token: Arc<Option<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Err(reply) = check_admin(authorization, &token) {
+        return Ok(reply);
+    }
+    match network_state.remove_post(&id).await {
+        Ok(removed) => {
+            tracing::info!("🛡️ Post {} retiré par l'administrateur", id);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"id": id, "removed": removed})),
+                StatusCode::OK,
+            ))
+        }
+        Err(e) => Ok(error_reply(StatusCode::INTERNAL_SERVER_ERROR, &e)),
+    }
+}
+
+fn error_reply(status: StatusCode, message: &str) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({"error": message})),
+        status,
+    )
 }
 
-async fn handle_websocket(ws: WebSocket, network_state: NetworkState, p2p_state: SharedState) {
+async fn create_reaction(
+    req: ReactionRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let reaction = match (req.author, req.timestamp, req.signature) {
+        (Some(author), Some(timestamp), Some(signature)) => Reaction {
+            post_id: req.post_id,
+            author,
+            reaction: req.reaction,
+            timestamp,
+            signature,
+        },
+        (None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            let mut reaction = Reaction {
+                post_id: req.post_id,
+                author: String::new(),
+                reaction: req.reaction,
+                timestamp: chrono::Utc::now().timestamp(),
+                signature: String::new(),
+            };
+            signing::sign_reaction(&mut reaction, &network_state.author_key);
+            reaction
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
+    };
+
+    if let Err(e) = validation::validate_reaction(&reaction) {
+        return Ok(error_reply(StatusCode::BAD_REQUEST, &e.to_string()));
+    }
+    if let Err(verdict) = network_state.moderation.check_reaction(&reaction) {
+        return Ok(error_reply(StatusCode::FORBIDDEN, &format!("refusé par la modération: {}", verdict)));
+    }
+    if let Err(scope) = check_limits(&network_state.rate_limits, remote, &reaction.author, Kind::Reaction) {
+        return Ok(error_reply(StatusCode::TOO_MANY_REQUESTS, &rate_limit_message(scope)));
+    }
+    if let Err(rejection) = network_state.known_post(&reaction.post_id) {
+        return Ok(rejection_reply(&rejection));
+    }
+
+    let post_id = reaction.post_id.clone();
+    if network_state.add_reaction(reaction.clone()).await {
+        let _ = ws_to_p2p_tx.send(NetworkMessage::Reaction(reaction));
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "post_id": post_id,
+            "reactions": network_state.reaction_counts(&post_id),
+        })),
+        StatusCode::OK,
+    ))
+}
+
+async fn create_edit(
+    req: EditRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let edit = match (req.author, req.timestamp, req.signature) {
+        (Some(author), Some(timestamp), Some(signature)) => Edit {
+            post_id: req.post_id,
+            author,
+            content: req.content,
+            timestamp,
+            signature,
+        },
+        (None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            let mut edit = Edit {
+                post_id: req.post_id,
+                author: String::new(),
+                content: req.content,
+                timestamp: chrono::Utc::now().timestamp(),
+                signature: String::new(),
+            };
+            signing::sign_edit(&mut edit, &network_state.author_key);
+            edit
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
+    };
+
+    let verdict = validation::validate_edit(&edit)
+        .and_then(|_| check_limits(&network_state.rate_limits, remote, &edit.author, Kind::Edit).map_err(Rejection::RateLimited));
+    if let Err(e) = verdict {
+        return Ok(rejection_reply(&e));
+    }
+    let post_id = edit.post_id.clone();
+    match network_state.apply_edit(edit.clone()).await {
+        Ok(applied) => {
+            if applied {
+                let _ = ws_to_p2p_tx.send(NetworkMessage::Edit(edit));
+            }
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"post_id": post_id, "applied": applied})),
+                StatusCode::OK,
+            ))
+        }
+        Err(e) => Ok(rejection_reply(&e)),
+    }
+}
+
+async fn create_delete(
+    req: DeleteRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let delete = match (req.author, req.timestamp, req.signature) {
+        (Some(author), Some(timestamp), Some(signature)) => Delete {
+            post_id: req.post_id,
+            author,
+            timestamp,
+            signature,
+        },
+        (None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            let mut delete = Delete {
+                post_id: req.post_id,
+                author: String::new(),
+                timestamp: chrono::Utc::now().timestamp(),
+                signature: String::new(),
+            };
+            signing::sign_delete(&mut delete, &network_state.author_key);
+            delete
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
+    };
+
+    let verdict = validation::validate_delete(&delete)
+        .and_then(|_| check_limits(&network_state.rate_limits, remote, &delete.author, Kind::Edit).map_err(Rejection::RateLimited));
+    if let Err(e) = verdict {
+        return Ok(rejection_reply(&e));
+    }
+    let post_id = delete.post_id.clone();
+    match network_state.apply_delete(delete.clone()).await {
+        Ok(applied) => {
+            if applied {
+                let _ = ws_to_p2p_tx.send(NetworkMessage::Delete(delete));
+            }
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"post_id": post_id, "deleted": applied})),
+                StatusCode::OK,
+            ))
+        }
+        Err(e) => Ok(rejection_reply(&e)),
+    }
+}
+
+async fn create_profile(
+    req: ProfileRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let profile = match (req.author, req.timestamp, req.signature) {
+        (Some(author), Some(timestamp), Some(signature)) => Profile {
+            author,
+            display_name: req.display_name,
+            bio: req.bio,
+            avatar: req.avatar,
+            dm_key: req.dm_key,
+            timestamp,
+            signature,
+        },
+        (None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            let mut profile = Profile {
+                author: String::new(),
+                display_name: req.display_name,
+                bio: req.bio,
+                avatar: req.avatar,
+                dm_key: req.dm_key,
+                timestamp: chrono::Utc::now().timestamp(),
+                signature: String::new(),
+            };
+            signing::sign_profile(&mut profile, &network_state.author_key);
+            profile
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
+    };
+
+    let verdict = validation::validate_profile(&profile)
+        .and_then(|_| network_state.moderation.check_profile(&profile).map_err(Rejection::Moderated))
+        .and_then(|_| check_limits(&network_state.rate_limits, remote, &profile.author, Kind::Profile).map_err(Rejection::RateLimited));
+    if let Err(e) = verdict {
+        return Ok(rejection_reply(&e));
+    }
+    // Un profil plus ancien que celui connu n'est pas relayé
+    if network_state.set_profile(profile.clone()).await {
+        let _ = ws_to_p2p_tx.send(NetworkMessage::Profile(profile.clone()));
+    }
+    match network_state.profile(&profile.author) {
+        Some(current) => Ok(warp::reply::with_status(warp::reply::json(&current), StatusCode::OK)),
+        None => Ok(error_reply(StatusCode::INTERNAL_SERVER_ERROR, "profil non enregistré")),
+    }
+}
+
+async fn get_profile(author: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    match network_state.profile(&author) {
+        Some(profile) => Ok(warp::reply::with_status(warp::reply::json(&profile), StatusCode::OK)),
+        None => Ok(error_reply(StatusCode::NOT_FOUND, "profil inconnu")),
+    }
+}
+
+async fn create_follow(
+    following: bool,
+    req: FollowRequest,
+    remote: Option<SocketAddr>,
+    authorization: Option<String>,
+    token: Arc<Option<String>>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let follow = match (req.author, req.timestamp, req.signature) {
+        (Some(author), Some(timestamp), Some(signature)) => Follow {
+            author,
+            target: req.target,
+            timestamp,
+            signature,
+        },
+        (None, None, None) => {
+            if let Err(reply) = check_node_signing(authorization, &token) {
+                return Ok(reply);
+            }
+            let mut follow = Follow {
+                author: String::new(),
+                target: req.target,
+                timestamp: chrono::Utc::now().timestamp(),
+                signature: String::new(),
+            };
+            signing::sign_follow(&mut follow, following, &network_state.author_key);
+            follow
+        }
+        _ => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "author, timestamp et signature doivent être fournis ensemble",
+            ));
+        }
+    };
+
+    let verdict = validation::validate_follow(&follow, following)
+        .and_then(|_| network_state.moderation.check_follow(&follow).map_err(Rejection::Moderated))
+        .and_then(|_| check_limits(&network_state.rate_limits, remote, &follow.author, Kind::Follow).map_err(Rejection::RateLimited));
+    if let Err(e) = verdict {
+        return Ok(rejection_reply(&e));
+    }
+    let author = follow.author.clone();
+    if network_state.set_follow(follow.clone(), following).await {
+        let message = if following { NetworkMessage::Follow(follow) } else { NetworkMessage::Unfollow(follow) };
+        let _ = ws_to_p2p_tx.send(message);
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({"author": author, "following": network_state.following(&author)})),
+        StatusCode::OK,
+    ))
+}
+
+async fn get_following(author: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&network_state.following(&author)))
+}
+
+async fn get_feed(author: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&network_state.feed(&author, crate::TIMELINE_LIMIT)))
+}
+
+async fn list_channels(network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&network_state.channels.list()))
+}
+
+/// Le canal reste suivi jusqu'au `leave` de l'administrateur, quelles que
+/// soient les sessions WebSocket qui le rejoignent ou le quittent
+async fn join_channel(
+    channel: String,
+    authorization: Option<String>,
+    network_state: NetworkState,
+    token: Arc<Option<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Err(reply) = check_admin(authorization, &token) {
+        return Ok(reply);
+    }
+    match network_state.channels.pin(&channel) {
+        Ok(()) => Ok(warp::reply::with_status(
+            warp::reply::json(&network_state.channels.list()),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(error_reply(StatusCode::BAD_REQUEST, &e.to_string())),
+    }
+}
+
+async fn leave_channel(
+    channel: String,
+    authorization: Option<String>,
+    network_state: NetworkState,
+    token: Arc<Option<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Err(reply) = check_admin(authorization, &token) {
+        return Ok(reply);
+    }
+    network_state.channels.unpin(&channel);
+    Ok(warp::reply::with_status(
+        warp::reply::json(&network_state.channels.list()),
+        StatusCode::OK,
+    ))
+}
+
+async fn get_channel_posts(channel: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    if !crate::channels::is_valid_name(&channel) {
+        return Ok(error_reply(StatusCode::BAD_REQUEST, "nom de canal invalide"));
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&network_state.channel_timeline(&channel, crate::TIMELINE_LIMIT)),
+        StatusCode::OK,
+    ))
+}
+
+async fn upload_media(
+    body: warp::hyper::body::Bytes,
+    remote: Option<SocketAddr>,
+    network_state: NetworkState,
+) -> Result<impl warp::Reply, Infallible> {
+    if let Some(Err(scope)) = remote.map(|addr| network_state.rate_limits.check_ip(addr.ip(), Kind::Media)) {
+        return Ok(error_reply(StatusCode::TOO_MANY_REQUESTS, &rate_limit_message(scope)));
+    }
+    let media = network_state.media.clone();
+    let stored = tokio::task::spawn_blocking(move || media.put(&body))
+        .await
+        .unwrap_or_else(|e| Err(MediaError::Io(e.into())));
+    match stored {
+        Ok(info) => {
+            tracing::info!("📤 Média {} stocké ({}, {} octets)", info.hash, info.mime, info.size);
+            Ok(warp::reply::with_status(warp::reply::json(&info), StatusCode::OK))
+        }
+        Err(MediaError::Io(e)) => {
+            tracing::error!("❌ Échec d'écriture d'un média: {}", e);
+            Ok(error_reply(StatusCode::INTERNAL_SERVER_ERROR, "stockage du média impossible"))
+        }
+        Err(e @ MediaError::TooLarge) => Ok(error_reply(StatusCode::PAYLOAD_TOO_LARGE, &e.to_string())),
+        Err(e) => Ok(error_reply(StatusCode::BAD_REQUEST, &e.to_string())),
+    }
+}
+
+/// Sert un blob ; s'il manque, le demande aux peers et attend au plus `media::FETCH_TIMEOUT`
+async fn serve_media(
+    hash: String,
+    remote: Option<SocketAddr>,
+    network_state: NetworkState,
+) -> Result<warp::reply::Response, Infallible> {
+    if !media::is_valid_hash(&hash) {
+        return Ok(error_reply(StatusCode::BAD_REQUEST, "hash invalide").into_response());
+    }
+    // Un blob absent coûte des requêtes aux peers : limite par client
+    if !network_state.media.contains(&hash) {
+        if let Some(Err(scope)) = remote.map(|addr| network_state.rate_limits.check_fetch(addr.ip())) {
+            return Ok(error_reply(StatusCode::TOO_MANY_REQUESTS, &rate_limit_message(scope)).into_response());
+        }
+    }
+    // S'abonner avant la demande pour ne pas manquer l'arrivée du blob
+    let mut fetched = network_state.media.subscribe();
+    let requested = match network_state.media.request(&hash, None) {
+        Ok(requested) => requested,
+        Err(e) => return Ok(error_reply(StatusCode::SERVICE_UNAVAILABLE, &e.to_string()).into_response()),
+    };
+    if requested {
+        let arrived = async {
+            while !network_state.media.contains(&hash) {
+                if let Err(broadcast::error::RecvError::Closed) = fetched.recv().await {
+                    break;
+                }
+            }
+        };
+        let _ = tokio::time::timeout(media::FETCH_TIMEOUT, arrived).await;
+    }
+    let media = network_state.media.clone();
+    let blob = {
+        let hash = hash.clone();
+        tokio::task::spawn_blocking(move || media.get(&hash)).await.unwrap_or_else(|e| Err(e.into()))
+    };
+    match blob {
+        Ok(Some(blob)) => Ok(media_response(blob)),
+        Ok(None) => Ok(error_reply(StatusCode::NOT_FOUND, "média introuvable").into_response()),
+        Err(e) => {
+            tracing::error!("❌ Lecture du média {} impossible: {}", hash, e);
+            Ok(error_reply(StatusCode::INTERNAL_SERVER_ERROR, "lecture du média impossible").into_response())
+        }
+    }
+}
+
+/// Type détecté sur le contenu, jamais interprété par le navigateur comme une
+/// page ; seuls les images, vidéos et sons s'affichent dans la page
+fn media_response(blob: Vec<u8>) -> warp::reply::Response {
+    use warp::http::header::{self, HeaderValue};
+
+    let mime = media::sniff(&blob);
+    let mut response = warp::reply::Response::new(blob.into());
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(mime));
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("default-src 'none'; sandbox"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=31536000, immutable"));
+    if !["image/", "video/", "audio/"].iter().any(|prefix| mime.starts_with(prefix)) {
+        headers.insert(header::CONTENT_DISPOSITION, HeaderValue::from_static("attachment"));
+    }
+    response
+}
+
+async fn get_posts_page(request: PageRequest, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    let parse = |cursor: Option<String>| cursor.map(|c| c.parse::<Cursor>()).transpose();
+    let (before, after) = match (parse(request.before), parse(request.after)) {
+        (Ok(before), Ok(after)) => (before, after),
+        (Err(e), _) | (_, Err(e)) => return Ok(error_reply(StatusCode::BAD_REQUEST, &e)),
+    };
+    let limit = request.limit.unwrap_or(crate::PAGE_LIMIT).clamp(1, crate::TIMELINE_LIMIT);
+    let page = network_state.page(request.author.as_deref(), before.as_ref(), after.as_ref(), limit);
+    Ok(warp::reply::with_status(warp::reply::json(&page), StatusCode::OK))
+}
+
+async fn search_posts(request: SearchRequest, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    let authors = request
+        .author
+        .iter()
+        .flat_map(|authors| authors.split(','))
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .map(str::to_string)
+        .collect();
+    let query = match Query::parse(&request.q, authors, request.since, request.until) {
+        Ok(query) => query,
+        Err(e) => return Ok(error_reply(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+    let limit = request.limit.unwrap_or(search::DEFAULT_RESULTS).clamp(1, search::MAX_RESULTS);
+    Ok(warp::reply::with_status(
+        warp::reply::json(&network_state.search(&query, limit)),
+        StatusCode::OK,
+    ))
+}
+
+/// Message privé déjà chiffré et signé par le navigateur de l'expéditeur
+async fn send_direct_message(
+    dm: DirectMessage,
+    remote: Option<SocketAddr>,
+    state: SharedState,
+) -> Result<impl warp::Reply, Infallible> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let verdict = validation::validate_direct_message(&dm)
+        .and_then(|_| network_state.moderation.check_direct_message(&dm).map_err(Rejection::Moderated))
+        .and_then(|_| check_limits(&network_state.rate_limits, remote, &dm.sender, Kind::DirectMessage).map_err(Rejection::RateLimited));
+    if let Err(e) = verdict {
+        return Ok(rejection_reply(&e));
+    }
+    let id = dm.id.clone();
+    if network_state.add_direct_message(dm.clone()).await {
+        let _ = ws_to_p2p_tx.send(NetworkMessage::DirectMessage(dm));
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({"id": id})),
+        StatusCode::OK,
+    ))
+}
+
+/// Les métadonnées des messages privés circulent en clair sur Gossipsub :
+/// ces routes ne révèlent rien de plus, le contenu reste chiffré
+async fn get_conversations(author: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&network_state.conversations(&author)))
+}
+
+async fn get_conversation(author: String, peer: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&network_state.conversation(&author, &peer)))
+}
+
+async fn get_history(id: String, network_state: NetworkState) -> Result<impl warp::Reply, Infallible> {
+    match network_state.history(&id) {
+        Some(history) => Ok(warp::reply::with_status(warp::reply::json(&history), StatusCode::OK)),
+        None => Ok(error_reply(StatusCode::NOT_FOUND, "post inconnu")),
+    }
+}
+
+/// Code HTTP correspondant à un refus de validation
+fn rejection_reply(rejection: &Rejection) -> warp::reply::WithStatus<warp::reply::Json> {
+    let status = match rejection {
+        Rejection::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        Rejection::NotAuthor | Rejection::Moderated(_) => StatusCode::FORBIDDEN,
+        Rejection::Deleted => StatusCode::GONE,
+        Rejection::UnknownPost => StatusCode::NOT_FOUND,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    let message = match rejection {
+        Rejection::RateLimited(scope) => rate_limit_message(*scope),
+        other => other.to_string(),
+    };
+    error_reply(status, &message)
+}
+
+/// Limites du type de message par IP distante (si connue) puis par auteur
+fn check_limits(limits: &RateLimits, remote: Option<SocketAddr>, author: &str, kind: Kind) -> Result<(), Scope> {
+    if let Some(addr) = remote {
+        limits.check_ip(addr.ip(), kind)?;
+    }
+    limits.check_author(author, kind)
+}
+
+fn rate_limit_message(scope: Scope) -> String {
+    format!("limite de débit atteinte ({}), réessayez plus tard", scope.as_str())
+}
+
+/// En mode fil personnel, ne laisse passer que les nouveaux posts des auteurs
+/// suivis ; la liste suit les abonnements de la clé de session, y compris
+/// ceux faits depuis un autre appareil
+fn feed_allows(followed: &mut HashSet<String>, session_key: &str, msg: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(msg) else {
+        return true;
+    };
+    match value["type"].as_str() {
+        Some("new_post") => value["post"]["author"].as_str().is_some_and(|author| followed.contains(author)),
+        Some("follow") if value["author"] == session_key => {
+            if let Some(target) = value["target"].as_str() {
+                if value["following"] == true {
+                    followed.insert(target.to_string());
+                } else {
+                    followed.remove(target);
+                }
+            }
+            true
+        }
+        _ => true,
+    }
+}
+
+/// Envoie par lots tous les messages privés en attente de `recipient`, chacun
+/// retiré de sa boîte une fois envoyé ; `false` si le client s'est déconnecté
+async fn send_pending_dms(
+    ws_tx: &mut SplitSink<WebSocket, Message>,
+    network_state: &NetworkState,
+    recipient: &str,
+) -> bool {
+    let mut previous: Option<String> = None;
+    loop {
+        let pending = network_state.pending_dms(recipient);
+        // Lot vide, ou identique au précédent si le stockage refuse de les retirer
+        let Some(first) = pending.first().map(|dm| dm.id.clone()) else {
+            return true;
+        };
+        if previous.as_ref() == Some(&first) {
+            return true;
+        }
+        let reply = serde_json::json!({"type": "pending_dms", "messages": pending});
+        if ws_tx.send(Message::text(reply.to_string())).await.is_err() {
+            return false;
+        }
+        for dm in &pending {
+            network_state.mark_delivered(dm);
+        }
+        previous = Some(first);
+    }
+}
+
+/// Message d'erreur renvoyé à un client WebSocket
+fn ws_error(message: &str) -> Message {
+    Message::text(serde_json::json!({"type": "error", "message": message}).to_string())
+}
+
+async fn handle_websocket(
+    ws: WebSocket,
+    remote: Option<SocketAddr>,
+    network_state: NetworkState,
+    p2p_state: SharedState,
+) {
     let (mut ws_tx, mut ws_rx) = ws.split();
-    let browser_peer_id = format!("browser-{}", &uuid::Uuid::new_v4().to_string()[..8]);
 
-    tracing::info!("🌐 Nouveau client WebSocket: {}", browser_peer_id);
+    // Le navigateur doit signer ce nonce pour prouver qu'il détient sa clé
+    let challenge = uuid::Uuid::new_v4().simple().to_string();
+    let relay_peer_id = network_state.local_peer_id.to_string();
+    // Clé publique (hex) du navigateur une fois authentifié
+    let mut session_key: Option<String> = None;
+    let mut session_buckets = network_state.rate_limits.session_buckets();
+    // Auteurs suivis (et la session elle-même) en mode fil personnel ; `None` pour la timeline globale
+    let mut feed: Option<HashSet<String>> = None;
+    // Canaux rejoints par cette session, quittés à la déconnexion
+    let mut joined: HashSet<String> = HashSet::new();
 
-    // Ajouter aux peers
-    network_state
-        .add_peer(PeerInfo {
-            peer_id: browser_peer_id.clone(),
-            address: "websocket".to_string(),
-            name: Some("Navigateur".to_string()),
-            is_browser: true,
-        })
-        .await;
+    tracing::info!("🌐 Nouveau client WebSocket (challenge {})", challenge);
 
     // S'abonner aux broadcasts
     let mut broadcast_rx = network_state.ws_broadcast.subscribe();
+    let mut dm_rx = network_state.dm_broadcast.subscribe();
 
     // Envoyer l'état initial
     let initial_state = {
         let peers = network_state.peers.read().await;
-        let posts = network_state.posts.read().await;
+        // Seulement la page la plus récente : la suite se demande par `load_more`
+        let page = network_state.page(None, None, None, crate::PAGE_LIMIT);
         serde_json::json!({
             "type": "init",
-            "peer_id": browser_peer_id,
+            "challenge": challenge,
+            "relay_peer_id": relay_peer_id,
             "peers": peers.values().collect::<Vec<_>>(),
-            "posts": posts.clone()
+            "posts": page.posts,
+            "cursor": page.before
         })
     };
 
@@ -190,6 +1385,7 @@ async fn handle_websocket(ws: WebSocket, network_state: NetworkState, p2p_state:
     {
         return;
     }
+    network_state.metrics.ws_connected();
 
     loop {
         tokio::select! {
@@ -199,44 +1395,403 @@ async fn handle_websocket(ws: WebSocket, network_state: NetworkState, p2p_state:
                         if let Ok(text) = msg.to_str() {
                             if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(text) {
                                 match ws_msg.msg_type.as_str() {
+                                    "auth" => {
+                                        if session_key.is_some() {
+                                            continue;
+                                        }
+                                        let (Some(public_key), Some(signature)) = (ws_msg.public_key, ws_msg.signature) else {
+                                            let _ = ws_tx.send(ws_error("auth incomplet")).await;
+                                            continue;
+                                        };
+                                        let message = signing::auth_challenge_bytes(&challenge, &relay_peer_id);
+                                        if !signing::verify_hex(&public_key, &message, &signature) {
+                                            tracing::warn!("🚫 Authentification WebSocket refusée pour {}", public_key);
+                                            let _ = ws_tx.send(ws_error("authentification refusée")).await;
+                                            continue;
+                                        }
+
+                                        network_state
+                                            .add_peer(PeerInfo {
+                                                peer_id: public_key.clone(),
+                                                address: "websocket".to_string(),
+                                                name: ws_msg.author_name,
+                                                is_browser: true,
+                                                ..Default::default()
+                                            })
+                                            .await;
+                                        let reply = serde_json::json!({"type": "auth_ok", "peer_id": public_key});
+                                        let _ = ws_tx.send(Message::text(reply.to_string())).await;
+                                        tracing::info!("🔓 Navigateur authentifié: {}", public_key);
+
+                                        // Messages privés reçus pendant l'absence du destinataire
+                                        if !send_pending_dms(&mut ws_tx, &network_state, &public_key).await {
+                                            break;
+                                        }
+                                        session_key = Some(public_key);
+                                    }
                                     "post" => {
-                                        // Supporter les deux formats de message
-                                        let (content, author_name, post_id, timestamp) = if let Some(data) = ws_msg.data {
-                                            // Format avec data: { type: "post", data: { content, author, ... } }
-                                            (
-                                                data.content.unwrap_or_default(),
-                                                data.author.unwrap_or_else(|| "Anonyme".to_string()),
-                                                data.id,
-                                                data.timestamp,
-                                            )
-                                        } else {
-                                            // Format direct: { type: "post", content, author_name }
-                                            (
-                                                ws_msg.content.unwrap_or_default(),
-                                                ws_msg.author_name.unwrap_or_else(|| "Anonyme".to_string()),
-                                                None,
-                                                None,
-                                            )
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
                                         };
-                                        
-                                        if !content.is_empty() {
-                                            let post = Post {
-                                                id: post_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
-                                                author: browser_peer_id.clone(),
-                                                author_name: author_name.clone(),
-                                                content: content.clone(),
-                                                timestamp: timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
-                                            };
+                                        let post = match (ws_msg.id, ws_msg.author, ws_msg.timestamp, ws_msg.signature) {
+                                            (Some(id), Some(author), Some(timestamp), Some(signature)) => Post {
+                                                id,
+                                                author,
+                                                author_name: ws_msg.author_name.unwrap_or_else(|| "Anonyme".to_string()),
+                                                content: ws_msg.content.unwrap_or_default(),
+                                                timestamp,
+                                                reply_to: ws_msg.reply_to,
+                                                root: ws_msg.root,
+                                                channel: ws_msg.channel,
+                                                attachments: ws_msg.attachments.unwrap_or_default(),
+                                                signature,
+                                            },
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("post non signé")).await;
+                                                continue;
+                                            }
+                                        };
+
+                                        if &post.author != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let metrics = &network_state.metrics;
+                                        if let Err(e) = validation::validate_post(&post) {
+                                            tracing::warn!("🚫 Post WebSocket {} rejeté: {}", post.id, e);
+                                            metrics.post_rejected(Source::WebSocket, &e);
+                                            let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            continue;
+                                        }
+                                        if let Some(channel) = post.channel.as_deref().filter(|c| !joined.contains(*c)) {
+                                            metrics.post_rejected(Source::WebSocket, &Rejection::BadChannel);
+                                            let _ = ws_tx.send(ws_error(&format!("rejoignez d'abord le canal {}", channel))).await;
+                                            continue;
+                                        }
+                                        if let Err(e) = network_state.check_attachments(&post).await {
+                                            metrics.post_rejected(Source::WebSocket, &Rejection::BadAttachment);
+                                            let _ = ws_tx.send(ws_error(&e)).await;
+                                            continue;
+                                        }
+                                        if let Err(verdict) = network_state.moderation.check_post(&post) {
+                                            tracing::warn!("🛡️ Post WebSocket {} refusé: {}", post.id, verdict);
+                                            let _ = ws_tx.send(ws_error(&format!("refusé par la modération: {}", verdict))).await;
+                                            metrics.post_rejected(Source::WebSocket, &Rejection::Moderated(verdict));
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        if let Err(scope) = limits
+                                            .check_session(&mut session_buckets, Kind::Post)
+                                            .and_then(|_| check_limits(limits, remote, &post.author, Kind::Post))
+                                        {
+                                            tracing::warn!("🐢 Post WebSocket {} refusé: limite {} atteinte", post.id, scope.as_str());
+                                            metrics.post_rejected(Source::WebSocket, &Rejection::RateLimited(scope));
+                                            let _ = ws_tx.send(ws_error(&rate_limit_message(scope))).await;
+                                            continue;
+                                        }
+                                        metrics.post_received(Source::WebSocket);
+
+                                        tracing::info!("📝 Post WebSocket: {} - {}", post.author_name, post.content);
 
-                                            // Ajouter localement
-                                            network_state.add_post(post.clone()).await;
+                                        // Ajouter localement
+                                        network_state.add_post(post.clone()).await;
+
+                                        // Relayer au réseau P2P
+                                        let state_guard = p2p_state.read().await;
+                                        let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                        let _ = ws_to_p2p_tx.send(NetworkMessage::Post(post));
+                                    }
+                                    "reaction" => {
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
+                                        };
+                                        let reaction = match (ws_msg.post_id, ws_msg.reaction, ws_msg.author, ws_msg.timestamp, ws_msg.signature) {
+                                            (Some(post_id), Some(reaction), Some(author), Some(timestamp), Some(signature)) => Reaction {
+                                                post_id,
+                                                author,
+                                                reaction,
+                                                timestamp,
+                                                signature,
+                                            },
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("réaction non signée")).await;
+                                                continue;
+                                            }
+                                        };
+                                        if &reaction.author != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        let verdict = validation::validate_reaction(&reaction)
+                                            .and_then(|_| network_state.moderation.check_reaction(&reaction).map_err(validation::Rejection::Moderated))
+                                            .and_then(|_| limits.check_session(&mut session_buckets, Kind::Reaction).map_err(validation::Rejection::RateLimited))
+                                            .and_then(|_| check_limits(limits, remote, &reaction.author, Kind::Reaction).map_err(validation::Rejection::RateLimited))
+                                            .and_then(|_| network_state.known_post(&reaction.post_id));
+                                        if let Err(e) = verdict {
+                                            tracing::warn!("🚫 Réaction WebSocket à {} refusée: {}", reaction.post_id, e);
+                                            let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            continue;
+                                        }
 
-                                            // Relayer au réseau P2P
+                                        // Une réaction plus ancienne que celle déjà connue n'est pas relayée
+                                        if network_state.add_reaction(reaction.clone()).await {
                                             let state_guard = p2p_state.read().await;
                                             let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
-                                            let _ = ws_to_p2p_tx.send(NetworkMessage::Post(post));
+                                            let _ = ws_to_p2p_tx.send(NetworkMessage::Reaction(reaction));
+                                        }
+                                    }
+                                    "dm" => {
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
+                                        };
+                                        let Ok(dm) = serde_json::from_str::<DirectMessage>(text) else {
+                                            let _ = ws_tx.send(ws_error("message privé incomplet")).await;
+                                            continue;
+                                        };
+                                        if &dm.sender != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        let verdict = validation::validate_direct_message(&dm)
+                                            .and_then(|_| network_state.moderation.check_direct_message(&dm).map_err(Rejection::Moderated))
+                                            .and_then(|_| limits.check_session(&mut session_buckets, Kind::DirectMessage).map_err(Rejection::RateLimited))
+                                            .and_then(|_| check_limits(limits, remote, &dm.sender, Kind::DirectMessage).map_err(Rejection::RateLimited));
+                                        if let Err(e) = verdict {
+                                            tracing::warn!("🚫 Message privé WebSocket {} refusé: {}", dm.id, e);
+                                            let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            continue;
+                                        }
 
-                                            tracing::info!("📝 Post WebSocket: {} - {}", author_name, content);
+                                        if network_state.add_direct_message(dm.clone()).await {
+                                            let state_guard = p2p_state.read().await;
+                                            let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                            let _ = ws_to_p2p_tx.send(NetworkMessage::DirectMessage(dm));
+                                        }
+                                    }
+                                    "join" => {
+                                        let Some(channel) = ws_msg.channel else {
+                                            continue;
+                                        };
+                                        if !joined.contains(&channel) {
+                                            // Chaque canal rejoint peut coûter un abonnement au relais
+                                            let Some(author_key) = &session_key else {
+                                                let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                                continue;
+                                            };
+                                            if joined.len() >= crate::channels::MAX_SESSION_CHANNELS {
+                                                let _ = ws_tx.send(ws_error(&crate::channels::ChannelError::TooManyForSession.to_string())).await;
+                                                continue;
+                                            }
+                                            let limits = &network_state.rate_limits;
+                                            if let Err(scope) = limits
+                                                .check_session(&mut session_buckets, Kind::Channel)
+                                                .and_then(|_| check_limits(limits, remote, author_key, Kind::Channel))
+                                            {
+                                                let _ = ws_tx.send(ws_error(&rate_limit_message(scope))).await;
+                                                continue;
+                                            }
+                                            if let Err(e) = network_state.channels.join(&channel) {
+                                                let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                                continue;
+                                            }
+                                            joined.insert(channel.clone());
+                                        }
+                                        let reply = serde_json::json!({
+                                            "type": "channel",
+                                            "channel": channel,
+                                            "posts": network_state.channel_timeline(&channel, crate::TIMELINE_LIMIT),
+                                        });
+                                        let _ = ws_tx.send(Message::text(reply.to_string())).await;
+                                    }
+                                    "leave" => {
+                                        if let Some(channel) = ws_msg.channel.filter(|c| joined.remove(c)) {
+                                            network_state.channels.leave(&channel);
+                                        }
+                                    }
+                                    "subscribe" => {
+                                        let (posts, cursor) = match (ws_msg.mode.as_deref(), &session_key) {
+                                            (Some("feed"), Some(author_key)) => {
+                                                let mut followed: HashSet<String> = network_state.following(author_key).into_iter().collect();
+                                                followed.insert(author_key.clone());
+                                                feed = Some(followed);
+                                                (network_state.feed(author_key, crate::TIMELINE_LIMIT), None)
+                                            }
+                                            (Some("feed"), None) => {
+                                                let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                                continue;
+                                            }
+                                            (Some("global"), _) => {
+                                                feed = None;
+                                                let page = network_state.page(None, None, None, crate::PAGE_LIMIT);
+                                                (page.posts, page.before)
+                                            }
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("mode inconnu")).await;
+                                                continue;
+                                            }
+                                        };
+                                        let reply = serde_json::json!({"type": "timeline", "mode": ws_msg.mode, "posts": posts, "cursor": cursor});
+                                        let _ = ws_tx.send(Message::text(reply.to_string())).await;
+                                    }
+                                    "load_more" => {
+                                        let before = match ws_msg.before.as_deref().map(str::parse::<Cursor>) {
+                                            Some(Ok(before)) => before,
+                                            Some(Err(e)) => {
+                                                let _ = ws_tx.send(ws_error(&e)).await;
+                                                continue;
+                                            }
+                                            None => {
+                                                let _ = ws_tx.send(ws_error("curseur manquant")).await;
+                                                continue;
+                                            }
+                                        };
+                                        let page = network_state.page(None, Some(&before), None, crate::PAGE_LIMIT);
+                                        let reply = serde_json::json!({"type": "more_posts", "posts": page.posts, "cursor": page.before});
+                                        let _ = ws_tx.send(Message::text(reply.to_string())).await;
+                                    }
+                                    "follow" | "unfollow" => {
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
+                                        };
+                                        let following = ws_msg.msg_type == "follow";
+                                        let follow = match (ws_msg.author, ws_msg.target, ws_msg.timestamp, ws_msg.signature) {
+                                            (Some(author), Some(target), Some(timestamp), Some(signature)) => Follow {
+                                                author,
+                                                target,
+                                                timestamp,
+                                                signature,
+                                            },
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("abonnement non signé")).await;
+                                                continue;
+                                            }
+                                        };
+                                        if &follow.author != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        let verdict = validation::validate_follow(&follow, following)
+                                            .and_then(|_| network_state.moderation.check_follow(&follow).map_err(Rejection::Moderated))
+                                            .and_then(|_| limits.check_session(&mut session_buckets, Kind::Follow).map_err(Rejection::RateLimited))
+                                            .and_then(|_| check_limits(limits, remote, &follow.author, Kind::Follow).map_err(Rejection::RateLimited));
+                                        if let Err(e) = verdict {
+                                            tracing::warn!("🚫 Abonnement WebSocket de {} refusé: {}", follow.author, e);
+                                            let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            continue;
+                                        }
+
+                                        if network_state.set_follow(follow.clone(), following).await {
+                                            let state_guard = p2p_state.read().await;
+                                            let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                            let message = if following { NetworkMessage::Follow(follow) } else { NetworkMessage::Unfollow(follow) };
+                                            let _ = ws_to_p2p_tx.send(message);
+                                        }
+                                    }
+                                    "profile" => {
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
+                                        };
+                                        let profile = match (ws_msg.author, ws_msg.display_name, ws_msg.timestamp, ws_msg.signature) {
+                                            (Some(author), Some(display_name), Some(timestamp), Some(signature)) => Profile {
+                                                author,
+                                                display_name,
+                                                bio: ws_msg.bio.unwrap_or_default(),
+                                                avatar: ws_msg.avatar,
+                                                dm_key: ws_msg.dm_key,
+                                                timestamp,
+                                                signature,
+                                            },
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("profil non signé")).await;
+                                                continue;
+                                            }
+                                        };
+                                        if &profile.author != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        let verdict = validation::validate_profile(&profile)
+                                            .and_then(|_| network_state.moderation.check_profile(&profile).map_err(Rejection::Moderated))
+                                            .and_then(|_| limits.check_session(&mut session_buckets, Kind::Profile).map_err(Rejection::RateLimited))
+                                            .and_then(|_| check_limits(limits, remote, &profile.author, Kind::Profile).map_err(Rejection::RateLimited));
+                                        if let Err(e) = verdict {
+                                            tracing::warn!("🚫 Profil WebSocket de {} refusé: {}", profile.author, e);
+                                            let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            continue;
+                                        }
+
+                                        if network_state.set_profile(profile.clone()).await {
+                                            let state_guard = p2p_state.read().await;
+                                            let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                            let _ = ws_to_p2p_tx.send(NetworkMessage::Profile(profile));
+                                        }
+                                    }
+                                    "edit" | "delete" => {
+                                        let Some(ref author_key) = session_key else {
+                                            let _ = ws_tx.send(ws_error("authentification requise")).await;
+                                            continue;
+                                        };
+                                        let message = match (ws_msg.msg_type.as_str(), ws_msg.post_id, ws_msg.author, ws_msg.timestamp, ws_msg.signature) {
+                                            ("edit", Some(post_id), Some(author), Some(timestamp), Some(signature)) => match ws_msg.content {
+                                                Some(content) => NetworkMessage::Edit(Edit { post_id, author, content, timestamp, signature }),
+                                                None => {
+                                                    let _ = ws_tx.send(ws_error("contenu manquant")).await;
+                                                    continue;
+                                                }
+                                            },
+                                            ("delete", Some(post_id), Some(author), Some(timestamp), Some(signature)) => {
+                                                NetworkMessage::Delete(Delete { post_id, author, timestamp, signature })
+                                            }
+                                            _ => {
+                                                let _ = ws_tx.send(ws_error("message non signé")).await;
+                                                continue;
+                                            }
+                                        };
+                                        let (post_id, author) = match &message {
+                                            NetworkMessage::Edit(edit) => (&edit.post_id, &edit.author),
+                                            NetworkMessage::Delete(delete) => (&delete.post_id, &delete.author),
+                                            _ => unreachable!(),
+                                        };
+                                        if author != author_key {
+                                            let _ = ws_tx.send(ws_error("l'auteur ne correspond pas à la session")).await;
+                                            continue;
+                                        }
+                                        let limits = &network_state.rate_limits;
+                                        let verdict = match &message {
+                                            NetworkMessage::Edit(edit) => validation::validate_edit(edit),
+                                            NetworkMessage::Delete(delete) => validation::validate_delete(delete),
+                                            _ => unreachable!(),
+                                        }
+                                        .and_then(|_| limits.check_session(&mut session_buckets, Kind::Edit).map_err(Rejection::RateLimited))
+                                        .and_then(|_| check_limits(limits, remote, author, Kind::Edit).map_err(Rejection::RateLimited));
+                                        let applied = match verdict {
+                                            Ok(()) => match &message {
+                                                NetworkMessage::Edit(edit) => network_state.apply_edit(edit.clone()).await,
+                                                NetworkMessage::Delete(delete) => network_state.apply_delete(delete.clone()).await,
+                                                _ => unreachable!(),
+                                            },
+                                            Err(e) => Err(e),
+                                        };
+                                        match applied {
+                                            Ok(true) => {
+                                                let state_guard = p2p_state.read().await;
+                                                let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                                let _ = ws_to_p2p_tx.send(message);
+                                            }
+                                            Ok(false) => {}
+                                            Err(e) => {
+                                                tracing::warn!("🚫 {} WebSocket de {} refusée: {}", ws_msg.msg_type, post_id, e);
+                                                let _ = ws_tx.send(ws_error(&e.to_string())).await;
+                                            }
                                         }
                                     }
                                     "ping" => {
@@ -253,19 +1808,67 @@ async fn handle_websocket(ws: WebSocket, network_state: NetworkState, p2p_state:
                 }
             }
 
+            dm = dm_rx.recv() => {
+                match dm {
+                    Ok(dm) => {
+                        let Some(author_key) = session_key.as_deref() else {
+                            continue;
+                        };
+                        if dm.recipient != author_key && dm.sender != author_key {
+                            continue;
+                        }
+                        let msg = serde_json::json!({"type": "dm", "message": dm});
+                        if ws_tx.send(Message::text(msg.to_string())).await.is_err() {
+                            break;
+                        }
+                        if dm.recipient == author_key {
+                            network_state.mark_delivered(&dm);
+                        }
+                    }
+                    // Messages manqués : toujours dans la boîte du destinataire, renvoyés depuis le stockage
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        network_state.metrics.broadcast_lagged("dm", skipped);
+                        if let Some(author_key) = session_key.as_deref() {
+                            if !send_pending_dms(&mut ws_tx, &network_state, author_key).await {
+                                break;
+                            }
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+
             broadcast = broadcast_rx.recv() => {
                 match broadcast {
                     Ok(msg) => {
+                        if let (Some(followed), Some(author_key)) = (feed.as_mut(), session_key.as_deref()) {
+                            if !feed_allows(followed, author_key, &msg) {
+                                continue;
+                            }
+                        }
                         if ws_tx.send(Message::text(msg)).await.is_err() {
                             break;
                         }
                     }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        network_state.metrics.broadcast_lagged("posts", skipped);
+                        break;
+                    }
                     Err(_) => break,
                 }
             }
         }
     }
 
-    network_state.remove_peer(&browser_peer_id).await;
-    tracing::info!("👋 Client WebSocket déconnecté: {}", browser_peer_id);
+    network_state.metrics.ws_disconnected();
+    for channel in &joined {
+        network_state.channels.leave(channel);
+    }
+
+    if let Some(public_key) = session_key {
+        network_state.remove_peer(&public_key).await;
+        tracing::info!("👋 Client WebSocket déconnecté: {}", public_key);
+    } else {
+        tracing::info!("👋 Client WebSocket anonyme déconnecté");
+    }
 }